@@ -0,0 +1,61 @@
+use std::fs;
+
+/// The paths an argument stands for. Shells usually expand globs before
+/// `lsr` sees them, but some don't and a quoted one gets through as is, so
+/// an argument with glob characters that names no file itself is expanded
+/// here, in alphabetical order. A pattern that matches nothing gives no
+/// paths at all; one that is not a valid pattern is left as it is.
+pub fn expand(target: &str) -> Vec<String> {
+    let literal = !target.contains(['*', '?', '[']) || fs::symlink_metadata(target).is_ok();
+    if literal {
+        return vec![target.to_string()];
+    }
+    let Ok(matches) = glob::glob(target) else {
+        return vec![target.to_string()];
+    };
+    // Like the shell, a trailing slash only matches directories, and it is
+    // kept so the headers read the way the pattern was written
+    let slash = if target.ends_with('/') { "/" } else { "" };
+    matches
+        .flatten()
+        .map(|path| format!("{}{slash}", path.display()))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::path::layout::Scratch;
+
+    const LAYOUT: &[&str] = &["b/", "a/", "x.toml", "y.toml", "z.rs"];
+
+    #[test]
+    fn glob_should_expand_in_order() {
+        let root = Scratch::new("lsr_args_glob", LAYOUT);
+        let at = |pattern: &str| format!("{}/{pattern}", root.display());
+
+        let files = expand(&at("*.toml"));
+        let dirs = expand(&at("*/"));
+
+        assert_eq!(files, vec![at("x.toml"), at("y.toml")]);
+        assert_eq!(dirs, vec![at("a/"), at("b/")]);
+    }
+
+    #[test]
+    fn existing_name_should_win_over_the_pattern() {
+        let root = Scratch::new("lsr_args_literal", LAYOUT);
+        let literal = format!("{}/[x].toml", root.display());
+        fs::write(&literal, "").unwrap();
+
+        let expanded = expand(&literal);
+
+        assert_eq!(expanded, vec![literal]);
+    }
+
+    #[test]
+    fn unmatched_glob_should_give_nothing() {
+        assert!(expand("/nonexistent-lsr-dir/*.nothing").is_empty());
+        assert_eq!(expand("plain"), vec!["plain"]);
+        assert_eq!(expand("bad[pattern"), vec!["bad[pattern"]);
+    }
+}
@@ -1,15 +1,618 @@
-use clap::Parser;
+use crate::path::{
+    layout,
+    lscolors::LsColors,
+    options::{
+        Align, Arrange, ColorBy, ColorMode, Column, Dedup, FileKind, Indicators, NameStyle,
+        Options, Output, SortKey, Traversal,
+    },
+    shuffle,
+    size::{self, BarScale, BlockSize, HumanSize, SizeStyle},
+    theme::Theme,
+    time::{self, Precision, TimeKind, TimeStyle},
+};
+use clap::{ArgAction, Parser, ValueEnum};
+use glob::Pattern;
+use std::{
+    fs,
+    path::PathBuf,
+    thread,
+    time::{Duration, SystemTime},
+};
+
+/// Ready-made combinations of the output options.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum Format {
+    /// Names only, the default.
+    #[default]
+    Compact,
+    /// The long format, same as -l.
+    Long,
+    /// MODE, BYTES, ISO 8601 time, NAME and link TARGET separated by tabs,
+    /// with no colors, padding or type markers.
+    Scripting,
+}
 
 #[derive(Parser)]
-#[command(author, version, about, long_about = None)]
+#[command(author, version, about, long_about = None, disable_help_flag = true)]
 pub struct Cli {
+    /// Print help
+    #[arg(long, action = ArgAction::Help)]
+    help: Option<bool>,
+    /// Files or directories to list, defaults to the current directory
+    #[arg(value_name = "PATH")]
+    pub paths: Vec<String>,
     /// Display with Date Modified and File Size
     #[arg(short, long)]
     pub long: bool,
+    /// Preset for the output: compact names, the long format, or tab-separated fields for scripts
+    #[arg(long, value_name = "FORMAT", default_value = "compact")]
+    pub format: Format,
+    /// List extended attributes and the length of their values below each entry (long mode)
+    #[arg(short = '@', long)]
+    pub xattr: bool,
+    /// Columns of the long format, in order, e.g. size,time,name
+    #[arg(
+        long,
+        value_name = "LIST",
+        value_delimiter = ',',
+        default_value = "perms,links,owner,size,time,name"
+    )]
+    pub columns: Vec<Column>,
+    /// Do not pad the long format's columns to line them up
+    #[arg(long)]
+    pub no_align: bool,
+    /// Put STRING between the long format's columns, with \t, \n and \\ as escapes
+    #[arg(long, value_name = "STRING", default_value = " ", value_parser = unescape)]
+    pub separator: String,
+    /// Show each file's extension in a column of its own, before the full name
+    #[arg(long)]
+    pub ext_column: bool,
+    /// Show allocated over apparent size after the size, e.g. "0.01x sparse" or "4.00x slack" (Unix only)
+    #[arg(long)]
+    pub frag: bool,
+    /// Leave the size column out of the long format
+    #[arg(long)]
+    pub no_size: bool,
+    /// Leave the time column out of the long format
+    #[arg(long)]
+    pub no_time: bool,
     /// Display all files including hidden ones
     #[arg(short, long)]
     pub all: bool,
+    /// Display only hidden files
+    #[arg(long, conflicts_with = "all")]
+    pub dotfiles_only: bool,
+    /// Hide files whose content repeats a file listed before them (reads every file)
+    #[arg(long, conflicts_with_all = ["tree", "show_dups_only"])]
+    pub dedup: bool,
+    /// Show only the files --dedup would hide
+    #[arg(long, conflicts_with = "tree")]
+    pub show_dups_only: bool,
     /// Display the files in a tree from the given directory
     #[arg(short, long, value_name = "DIR")]
     pub tree: Option<String>,
+    /// Show only directories in the --tree, empty ones included
+    #[arg(long, requires = "tree")]
+    pub dirs_only: bool,
+    /// Name each entry of the --tree by its path from the tree's root, e.g. "src/path/tree.rs"
+    #[arg(long, requires = "tree")]
+    pub paths_relative_in_tree: bool,
+    /// Hide entries matching the glob PATTERN unless -a is given; adds to .lsrignore
+    #[arg(long, value_name = "PATTERN", value_parser = Pattern::new)]
+    pub ignore: Vec<Pattern>,
+    /// Show only entries of these types, like find -type: f(ile), d(irectory), l(ink), p(ipe), s(ocket), b(lock) or c(har) device
+    #[arg(
+        long = "type",
+        value_name = "TYPES",
+        value_delimiter = ',',
+        conflicts_with = "tree"
+    )]
+    pub kinds: Vec<FileKind>,
+    /// Show only entries modified within DURATION, e.g. 15m, 2h or 3d
+    #[arg(long, value_name = "DURATION", value_parser = time::parse_duration)]
+    pub modified_within: Option<Duration>,
+    /// Show only entries git says changed since REF, e.g. HEAD~5 or main
+    #[arg(long, value_name = "REF")]
+    pub since: Option<String>,
+    /// List subdirectories recursively
+    #[arg(short = 'R', long)]
+    pub recursive: bool,
+    /// Visit subdirectories depth-first (like ls -R) or breadth-first
+    #[arg(long, value_name = "ORDER", default_value = "dfs")]
+    pub traversal: Traversal,
+    /// Go at most N levels deep with -R or --tree, 1 being only the given directory
+    #[arg(long, value_name = "N", value_parser = clap::value_parser!(u16).range(1..))]
+    pub max_depth: Option<u16>,
+    /// With -R, indent each level by N spaces instead of separating directories with blank lines
+    #[arg(long, value_name = "N", requires = "recursive")]
+    pub indent: Option<usize>,
+    /// Never show the spinner on stderr during slow -R or --tree scans
+    #[arg(long)]
+    pub no_progress: bool,
+    /// Descend into symlinked directories with -R, skipping ones already visited
+    #[arg(short = 'L', long, visible_alias = "dereference")]
+    pub follow_symlinks: bool,
+    /// Follow symlinks named on the command line, even in long mode
+    #[arg(short = 'H', long)]
+    pub dereference_command_line: bool,
+    /// List directory arguments themselves instead of their contents
+    #[arg(short = 'd', long)]
+    pub no_expand: bool,
+    /// List the entries of all directory arguments together, each prefixed with its directory
+    #[arg(long, conflicts_with_all = ["recursive", "no_expand"])]
+    pub merge: bool,
+    /// List the contents of zip and tar archive arguments like directories
+    #[arg(long)]
+    pub archives: bool,
+    /// Print "(empty)" when there are no entries to show
+    #[arg(long)]
+    pub empty_message: bool,
+    /// Lay the output out for exactly N columns instead of $COLUMNS or the terminal's width
+    #[arg(long, value_name = "N", value_parser = clap::value_parser!(u16).range(1..))]
+    pub output_width: Option<u16>,
+    /// Lay names out in exactly N grid columns whatever the width (not the long format's --columns)
+    #[arg(
+        long,
+        value_name = "N",
+        value_parser = clap::value_parser!(u16).range(1..),
+        conflicts_with_all = ["one_per_line", "zero"]
+    )]
+    pub entries_per_line: Option<u16>,
+    /// List one entry per line
+    #[arg(short = '1')]
+    pub one_per_line: bool,
+    /// End each line with NUL instead of a newline, one entry per line
+    #[arg(long)]
+    pub zero: bool,
+    /// Write full paths under your home directory starting with ~, in names and headers
+    #[arg(long)]
+    pub collapse_home: bool,
+    /// Cut names longer than N bytes short with …, so one can't stretch the columns; 0 never cuts
+    #[arg(long, value_name = "N", default_value_t = 255)]
+    pub max_name_bytes: usize,
+    /// Make the name column N characters wide, ending longer names in …
+    #[arg(long, value_name = "N", value_parser = clap::value_parser!(u16).range(1..))]
+    pub name_width: Option<u16>,
+    /// Show each entry as an absolute path, keeping symlinks in it as they are
+    #[arg(long)]
+    pub absolute: bool,
+    /// Show each entry as an absolute path with every symlink resolved
+    #[arg(long)]
+    pub canonical: bool,
+    /// Show each entry as a path relative to DIR, or absolute when it is outside DIR
+    #[arg(long, value_name = "DIR", value_parser = existing_dir)]
+    pub relative_to: Option<PathBuf>,
+    /// Separate names with commas, filling each line
+    #[arg(short = 'm')]
+    pub commas: bool,
+    /// Fill each line with names instead of aligning them in columns
+    #[arg(long, overrides_with = "commas")]
+    pub fill: bool,
+    /// Append '*' to executable files, without the other -F indicators
+    #[arg(long)]
+    pub append_exec_suffix: bool,
+    /// Append '/' to directories, '*' to executables and '@' to symlinks
+    #[arg(short = 'F', long)]
+    pub classify: bool,
+    /// With -F, mark symlinks to directories with '/' and to executables with '*' instead of '@'
+    #[arg(long, requires = "classify")]
+    pub classify_targets: bool,
+    /// Show how many entries each directory holds, e.g. "src [14]"
+    #[arg(long)]
+    pub child_count: bool,
+    /// Follow directories with nothing to show with "(empty)", dotfiles counting only with -a, or "(?)" when unreadable
+    #[arg(long)]
+    pub mark_empty: bool,
+    /// Print a summary of how many directories, files and symlinks were listed
+    #[arg(long)]
+    pub count: bool,
+    /// Print only the number of entries that would be listed, one line per directory
+    #[arg(
+        long,
+        conflicts_with_all = ["recursive", "tree", "yaml", "toml", "jsonl", "shell", "watch"]
+    )]
+    pub count_only: bool,
+    /// Print the combined size of the listed entries
+    #[arg(long)]
+    pub total: bool,
+    /// Name the largest file and the oldest entry below the listing
+    #[arg(long)]
+    pub highlights: bool,
+    /// Include hidden files in --count and --total even without --all
+    #[arg(long)]
+    pub count_all: bool,
+    /// Report names that aren't valid UTF-8 as errors and skip them, instead of showing them lossily
+    #[arg(long)]
+    pub strict_utf8: bool,
+    /// Print names without reading any metadata, the fastest listing; sorting by size or time keeps the directory order
+    #[arg(
+        long,
+        conflicts_with_all = ["long", "full_time", "format", "total", "highlights", "dir_sizes", "size_blocks", "modified_within", "yaml", "toml", "jsonl"]
+    )]
+    pub names_only: bool,
+    /// Stat the entries of big directories on up to N threads; defaults to the number of CPUs
+    #[arg(long, value_name = "N", value_parser = clap::value_parser!(u16).range(1..))]
+    pub threads: Option<u16>,
+    /// Do not report unreadable entries on stderr (the exit code still reflects them)
+    #[arg(long)]
+    pub quiet: bool,
+    /// Show sizes in a fixed unit: K, M, G (1024-based), KB, MB, GB (1000-based) or a byte count
+    #[arg(
+        long,
+        value_name = "SIZE",
+        value_parser = BlockSize::parse,
+        overrides_with_all = ["human_readable", "si"]
+    )]
+    pub block_size: Option<BlockSize>,
+    /// Show sizes like 1.0K, 234M or 2.0G, in powers of 1024
+    #[arg(short = 'h', long, overrides_with_all = ["block_size", "si"])]
+    pub human_readable: bool,
+    /// Like -h, but in powers of 1000 with SI suffixes, e.g. 1.0k
+    #[arg(long, overrides_with_all = ["block_size", "human_readable"])]
+    pub si: bool,
+    /// Show every size as an exact number of bytes, overriding the options above
+    #[arg(long)]
+    pub bytes: bool,
+    /// In the long format, show each size both in bytes and human-readable, e.g. "1048576 (1.0M)"
+    #[arg(long, requires = "long", conflicts_with_all = ["bytes", "block_size"])]
+    pub size_both: bool,
+    /// Show the space files take on disk, which is less for sparse files
+    #[arg(long, overrides_with = "apparent_size")]
+    pub actual_size: bool,
+    /// Print only the total size of each argument and its name, like du -s
+    #[arg(long)]
+    pub summarize: bool,
+    /// End with the size of each directory argument, or with one, of each directory in it, biggest first
+    #[arg(long, conflicts_with_all = ["tree", "summarize"])]
+    pub group_sizes: bool,
+    /// Show directories with the size of everything below them, like du: hard links count once
+    #[arg(long)]
+    pub dir_sizes: bool,
+    /// Start each line with the blocks the entry takes on disk (1024 bytes or --block-size), with a total (Unix only)
+    #[arg(short = 's', long)]
+    pub size_blocks: bool,
+    /// Head the long format with a "total" line of blocks like ls -l, in 1024 bytes or --block-size (Unix only)
+    #[arg(long, requires = "long")]
+    pub blocks_total: bool,
+    /// Show the length of files, the default
+    #[arg(long, overrides_with = "actual_size")]
+    pub apparent_size: bool,
+    /// Alignment of the name column
+    #[arg(long, value_name = "SIDE", default_value = "left")]
+    pub align_name: Align,
+    /// Alignment of the size column
+    #[arg(long, value_name = "SIDE", default_value = "right")]
+    pub align_size: Align,
+    /// Which time to show and to sort by with --sort=time: modification, access or change
+    #[arg(long, value_name = "KIND", default_value = "modification")]
+    pub time: TimeKind,
+    /// How to write timestamps
+    #[arg(long, value_name = "STYLE", default_value = "default")]
+    pub time_style: TimeStyle,
+    /// Resolution of the default time style: minutes, seconds or milliseconds
+    #[arg(long, value_name = "UNIT", default_value = "min")]
+    pub precision: Precision,
+    /// Long listing with full timestamps, same as -l --time-style=full-iso
+    #[arg(long)]
+    pub full_time: bool,
+    /// Write timestamps in UTC instead of the local timezone
+    #[arg(long)]
+    pub utc: bool,
+    /// Sort entries by this key, "none" keeps the directory order
+    #[arg(long, value_name = "KEY", default_value = "name")]
+    pub sort: SortKey,
+    /// Shuffle the same way as any other run with this seed, for --sort=random
+    #[arg(long, value_name = "N")]
+    pub seed: Option<u64>,
+    /// Do not sort, same as --sort=none
+    #[arg(short = 'U')]
+    pub unsorted: bool,
+    /// Sort numbers in names by value, chunk_9 before chunk_10, same as --sort=version
+    #[arg(short = 'v', long, conflicts_with = "unsorted")]
+    pub numeric_sort: bool,
+    /// Reverse the order of the sort
+    #[arg(short, long)]
+    pub reverse: bool,
+    /// Draw a bar after each file's size, scaled to the largest file (long mode)
+    #[arg(long)]
+    pub bars: bool,
+    /// Scale of the --bars: linear, or log so small files stay visible next to huge ones
+    #[arg(long, value_name = "SCALE", default_value = "linear")]
+    pub bar_scale: BarScale,
+    /// Spell size units as one letter (short, e.g. 1K) or in full (long, e.g. 1KB)
+    #[arg(long, value_name = "STYLE", default_value = "long")]
+    pub size_style: SizeStyle,
+    /// When to use colors; auto also honors NO_COLOR, CLICOLOR and CLICOLOR_FORCE
+    #[arg(long, value_name = "WHEN", default_value = "auto")]
+    pub color: ColorMode,
+    /// Colors for dark or light backgrounds, or mono for bold and underline only
+    #[arg(long, value_name = "THEME", default_value = "dark")]
+    pub theme: Theme,
+    /// Color names by type, by extension, by age like --heatmap, or not at all
+    #[arg(long, value_name = "STRATEGY", default_value = "type")]
+    pub color_by: ColorBy,
+    /// Color sizes on a gradient up to the largest file; needs a truecolor terminal
+    #[arg(long)]
+    pub color_scale: bool,
+    /// Color timestamps by age: today, this week, this month, older
+    #[arg(long)]
+    pub heatmap: bool,
+    /// Color the times of entries changed within DURATION, e.g. 1h, in bold green
+    #[arg(
+        long,
+        value_name = "DURATION",
+        value_parser = time::parse_duration,
+        conflicts_with = "heatmap"
+    )]
+    pub recent: Option<Duration>,
+    /// Print the listing as YAML
+    #[arg(long)]
+    pub yaml: bool,
+    /// Print the listing as TOML, an array of tables under "files"
+    #[arg(long, conflicts_with = "yaml")]
+    pub toml: bool,
+    /// Describe the options in effect on stderr before listing
+    #[arg(long)]
+    pub explain: bool,
+    /// Print one JSON object per entry and line, streaming directories unsorted as they are read
+    #[arg(long, conflicts_with_all = ["yaml", "toml"])]
+    pub jsonl: bool,
+    /// Print the --tree as nested JSON objects, with the children of each directory under "children"
+    #[arg(long, requires = "tree", conflicts_with_all = ["yaml", "toml", "jsonl"])]
+    pub json: bool,
+    /// Print the names on one line as single-quoted shell words, e.g. for `eval "rm $(lsr --shell)"`
+    #[arg(long, conflicts_with_all = ["yaml", "toml", "jsonl", "tree", "long"])]
+    pub shell: bool,
+    /// Clear the screen and list the directories again every --interval, until Ctrl-C
+    #[arg(
+        long,
+        conflicts_with_all = ["recursive", "tree", "merge", "summarize", "yaml", "toml", "jsonl", "shell"]
+    )]
+    pub watch: bool,
+    /// Time between --watch refreshes, e.g. 10s or 1m
+    #[arg(long, value_name = "DURATION", value_parser = time::parse_duration, default_value = "2s")]
+    pub interval: Duration,
+    /// With --watch, color entries that are new green, changed yellow and gone red and struck through
+    #[arg(long, requires = "watch")]
+    pub diff: bool,
+}
+
+impl Cli {
+    /// `--columns` with `--no-size` and `--no-time` taken out, the extension
+    /// put in front of the name for `--ext-column` and the allocation ratio
+    /// after the size for `--frag`.
+    fn columns(&self) -> Vec<Column> {
+        let mut columns: Vec<Column> = self
+            .columns
+            .iter()
+            .copied()
+            .filter(|column| match column {
+                Column::Size => !self.no_size,
+                Column::Time => !self.no_time,
+                _ => true,
+            })
+            .collect();
+        if self.ext_column && !columns.contains(&Column::Ext) {
+            let at = columns
+                .iter()
+                .position(|&column| column == Column::Name)
+                .unwrap_or(columns.len());
+            columns.insert(at, Column::Ext);
+        }
+        if self.frag && !columns.contains(&Column::Frag) {
+            let at = columns
+                .iter()
+                .position(|&column| column == Column::Size)
+                .map_or(columns.len().saturating_sub(1), |size| size + 1);
+            columns.insert(at, Column::Frag);
+        }
+        columns
+    }
+
+    pub fn options(&self) -> Options {
+        let options = self.flag_options();
+        match self.format {
+            Format::Compact => options,
+            Format::Long => Options {
+                long: true,
+                ..options
+            },
+            Format::Scripting => Options {
+                long: true,
+                columns: vec![
+                    Column::Perms,
+                    Column::Size,
+                    Column::Time,
+                    Column::Name,
+                    Column::Target,
+                ],
+                aligned: false,
+                separator: "\t".to_string(),
+                raw_bytes: true,
+                bars: false,
+                indicators: Indicators::None,
+                time_style: TimeStyle::Iso8601,
+                color: ColorMode::Never,
+                ..options
+            },
+        }
+    }
+
+    /// The options as the individual flags set them, before `--format`.
+    fn flag_options(&self) -> Options {
+        Options {
+            all: self.all,
+            dotfiles_only: self.dotfiles_only,
+            ignore: self.ignore.clone(),
+            kinds: self.kinds.clone(),
+            modified_since: self
+                .modified_within
+                .and_then(|within| SystemTime::now().checked_sub(within)),
+            since_revision: self.since.clone(),
+            changed: None,
+            virtual_fs: false,
+            dedup: if self.dedup {
+                Some(Dedup::HideCopies)
+            } else if self.show_dups_only {
+                Some(Dedup::CopiesOnly)
+            } else {
+                None
+            },
+            strict_utf8: self.strict_utf8,
+            names_only: self.names_only,
+            xattrs: self.xattr,
+            max_name_bytes: (self.max_name_bytes > 0).then_some(self.max_name_bytes),
+            name_width: self.name_width.map(usize::from),
+            collapse_home: self.collapse_home,
+            relative_to: self.relative_to.clone(),
+            name_style: if self.canonical {
+                NameStyle::Canonical
+            } else if self.absolute || self.relative_to.is_some() {
+                NameStyle::Absolute
+            } else {
+                NameStyle::Base
+            },
+            zero: self.zero,
+            threads: self.threads.map_or_else(
+                || thread::available_parallelism().map_or(1, usize::from),
+                usize::from,
+            ),
+            long: self.long || self.full_time,
+            columns: self.columns(),
+            aligned: !self.no_align,
+            separator: self.separator.clone(),
+            tree: self.tree.clone(),
+            dirs_only: self.dirs_only,
+            tree_relative: self.paths_relative_in_tree,
+            recursive: self.recursive,
+            traversal: self.traversal,
+            follow_symlinks: self.follow_symlinks,
+            max_depth: self.max_depth.map(usize::from),
+            margin: 0,
+            empty_message: self.empty_message,
+            child_count: self.child_count,
+            mark_empty: self.mark_empty,
+            watch_diff: self.watch && self.diff,
+            indicators: if self.classify {
+                Indicators::Classify
+            } else if self.append_exec_suffix {
+                Indicators::Executables
+            } else {
+                Indicators::None
+            },
+            classify_targets: self.classify_targets,
+            count: self.count,
+            total: self.total,
+            highlights: self.highlights,
+            count_all: self.count_all,
+            arrange: if self.commas {
+                Arrange::Commas
+            } else if self.fill {
+                Arrange::Fill
+            } else {
+                Arrange::Grid
+            },
+            grid_columns: self.entries_per_line.map(usize::from),
+            width: if self.one_per_line || self.zero {
+                None
+            } else {
+                self.output_width
+                    .map(usize::from)
+                    .or_else(layout::terminal_width)
+            },
+            block_size: self.block_size,
+            human: if self.si {
+                Some(HumanSize::Si)
+            } else if self.human_readable {
+                Some(HumanSize::Binary)
+            } else {
+                None
+            },
+            raw_bytes: self.bytes,
+            size_both: self.size_both,
+            actual_size: self.actual_size,
+            size_blocks: self.size_blocks,
+            blocks_total: self.blocks_total,
+            dir_sizes: self.dir_sizes,
+            bars: self.bars,
+            bar_scale: self.bar_scale,
+            size_style: self.size_style,
+            time_kind: self.time,
+            time_style: if self.full_time {
+                TimeStyle::FullIso
+            } else {
+                self.time_style
+            },
+            time_precision: self.precision,
+            utc: self.utc,
+            name_align: self.align_name,
+            size_align: self.align_size,
+            reverse: self.reverse,
+            seed: self.seed.unwrap_or_else(shuffle::fresh_seed),
+            sort: if self.unsorted {
+                SortKey::None
+            } else if self.numeric_sort {
+                SortKey::Version
+            } else {
+                self.sort
+            },
+            color: self.color,
+            theme: self.theme,
+            color_by: self.color_by,
+            ls_colors: LsColors::from_env(),
+            // Without truecolor the gradient would come out as noise, so keep
+            // the magnitude colors
+            color_scale: self.color_scale && size::truecolor_supported(),
+            heatmap: self.heatmap,
+            recent_since: self
+                .recent
+                .and_then(|recent| SystemTime::now().checked_sub(recent)),
+            output: if self.yaml {
+                Output::Yaml
+            } else if self.toml {
+                Output::Toml
+            } else if self.jsonl {
+                Output::JsonLines
+            } else if self.json {
+                Output::Json
+            } else if self.count_only {
+                Output::Count
+            } else if self.shell {
+                Output::Shell
+            } else {
+                Output::Text
+            },
+        }
+    }
+}
+
+/// The directory `value` names with every symlink resolved, so that a base
+/// that doesn't exist is caught here rather than matching nothing.
+fn existing_dir(value: &str) -> Result<PathBuf, String> {
+    match fs::canonicalize(value) {
+        Ok(dir) if dir.is_dir() => Ok(dir),
+        Ok(_) => Err("not a directory".to_string()),
+        Err(error) => Err(error.to_string()),
+    }
+}
+
+/// Turns `\t`, `\n` and `\\` into the characters they stand for, since
+/// shells make a literal tab awkward to pass.
+fn unescape(value: &str) -> Result<String, String> {
+    let mut out = String::new();
+    let mut chars = value.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            out.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('t') => out.push('\t'),
+            Some('n') => out.push('\n'),
+            Some('\\') => out.push('\\'),
+            Some(other) => return Err(format!("unknown escape '\\{other}'")),
+            None => return Err("trailing '\\'".to_string()),
+        }
+    }
+    Ok(out)
 }
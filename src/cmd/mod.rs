@@ -1 +1,5 @@
+pub mod args;
 pub mod cli;
+pub mod progress;
+pub mod status;
+pub mod watch;
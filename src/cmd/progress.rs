@@ -0,0 +1,108 @@
+use std::{
+    io::{self, IsTerminal, Write},
+    time::{Duration, Instant},
+};
+
+/// Scans shorter than this finish without a spinner ever flashing up.
+const THRESHOLD: Duration = Duration::from_millis(500);
+/// Redrawing on every directory would cost more than the scan itself.
+const REDRAW: Duration = Duration::from_millis(100);
+const FRAMES: [char; 4] = ['|', '/', '-', '\\'];
+
+/// Spinner on stderr counting the entries a slow recursive scan has gone
+/// through, so it doesn't look hung. It only ever shows on a terminal, and
+/// `clear` wipes it before anything is printed to stdout.
+pub struct Progress {
+    enabled: bool,
+    started: Instant,
+    drawn_at: Option<Instant>,
+    scanned: usize,
+    frame: usize,
+}
+
+impl Progress {
+    /// `enabled` is what the user asked for; stderr must also be a terminal.
+    pub fn new(enabled: bool) -> Self {
+        Progress {
+            enabled: enabled && io::stderr().is_terminal(),
+            started: Instant::now(),
+            drawn_at: None,
+            scanned: 0,
+            frame: 0,
+        }
+    }
+
+    /// Counts `entries` more and redraws if it's time to.
+    pub fn add(&mut self, entries: usize) {
+        self.scanned += entries;
+        let now = Instant::now();
+        if self.due(now) {
+            self.frame = (self.frame + 1) % FRAMES.len();
+            eprint!("\r{} {} scanned", FRAMES[self.frame], self.scanned);
+            let _ = io::stderr().flush();
+            self.drawn_at = Some(now);
+        }
+    }
+
+    fn due(&self, now: Instant) -> bool {
+        self.enabled
+            && now.duration_since(self.started) >= THRESHOLD
+            && self
+                .drawn_at
+                .is_none_or(|drawn_at| now.duration_since(drawn_at) >= REDRAW)
+    }
+
+    /// Erases the spinner line, if one was drawn.
+    pub fn clear(&mut self) {
+        if self.drawn_at.take().is_some() {
+            eprint!("\r\x1b[2K");
+            let _ = io::stderr().flush();
+        }
+    }
+}
+
+impl Drop for Progress {
+    fn drop(&mut self) {
+        self.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn progress(enabled: bool) -> Progress {
+        Progress {
+            enabled,
+            started: Instant::now(),
+            drawn_at: None,
+            scanned: 0,
+            frame: 0,
+        }
+    }
+
+    #[test]
+    fn quick_scans_should_not_draw() {
+        let progress = progress(true);
+
+        assert!(!progress.due(progress.started + THRESHOLD / 2));
+        assert!(progress.due(progress.started + THRESHOLD));
+    }
+
+    #[test]
+    fn redraws_should_be_throttled() {
+        let mut progress = progress(true);
+        let drawn_at = progress.started + THRESHOLD;
+        progress.drawn_at = Some(drawn_at);
+
+        assert!(!progress.due(drawn_at + REDRAW / 2));
+        assert!(progress.due(drawn_at + REDRAW));
+    }
+
+    #[test]
+    fn disabled_progress_should_never_draw() {
+        let progress = progress(false);
+
+        assert!(!progress.due(progress.started + THRESHOLD * 10));
+    }
+}
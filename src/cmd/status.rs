@@ -0,0 +1,57 @@
+use std::{fmt::Display, process::ExitCode};
+
+/// Outcome of a run, mirroring the exit codes of GNU `ls`. Variants are
+/// ordered by severity so the worst one seen can be kept with `max`.
+#[derive(Debug, Default, PartialEq, Eq, PartialOrd, Ord, Clone, Copy)]
+pub enum Status {
+    /// Everything was listed.
+    #[default]
+    Success,
+    /// Some entries could not be read, but the listing went through.
+    Minor,
+    /// A directory could not be listed at all.
+    Serious,
+}
+
+impl Status {
+    pub fn code(self) -> u8 {
+        match self {
+            Status::Success => 0,
+            Status::Minor => 1,
+            Status::Serious => 2,
+        }
+    }
+}
+
+impl From<Status> for ExitCode {
+    fn from(status: Status) -> Self {
+        ExitCode::from(status.code())
+    }
+}
+
+/// Writes an error to stderr, so it never ends up in piped output, unless
+/// `quiet` is set.
+pub fn report(quiet: bool, message: impl Display) {
+    if !quiet {
+        eprintln!("lsr: {message}");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn status_codes_should_match_ls() {
+        assert_eq!(Status::Success.code(), 0);
+        assert_eq!(Status::Minor.code(), 1);
+        assert_eq!(Status::Serious.code(), 2);
+    }
+
+    #[test]
+    fn worst_status_should_win() {
+        assert_eq!(Status::Success.max(Status::Minor), Status::Minor);
+        assert_eq!(Status::Serious.max(Status::Minor), Status::Serious);
+        assert_eq!(Status::default(), Status::Success);
+    }
+}
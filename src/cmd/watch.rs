@@ -0,0 +1,42 @@
+use std::{
+    sync::atomic::{AtomicBool, Ordering},
+    thread,
+    time::{Duration, Instant},
+};
+
+/// Ctrl-C is checked this often while waiting for the next refresh.
+const POLL: Duration = Duration::from_millis(100);
+
+static INTERRUPTED: AtomicBool = AtomicBool::new(false);
+
+/// Makes Ctrl-C end `--watch` once the screen being drawn is finished,
+/// rather than cutting it off halfway. Elsewhere than Unix it still stops
+/// the program the usual way.
+pub fn catch_interrupt() {
+    #[cfg(unix)]
+    // SAFETY: the handler only stores to an atomic, which is signal safe
+    unsafe {
+        libc::signal(
+            libc::SIGINT,
+            on_interrupt as extern "C" fn(libc::c_int) as libc::sighandler_t,
+        );
+    }
+}
+
+#[cfg(unix)]
+extern "C" fn on_interrupt(_signal: libc::c_int) {
+    INTERRUPTED.store(true, Ordering::SeqCst);
+}
+
+/// Sleeps for `interval`, or until Ctrl-C, in which case it returns `false`.
+pub fn wait(interval: Duration) -> bool {
+    let until = Instant::now() + interval;
+    while !INTERRUPTED.load(Ordering::SeqCst) {
+        let left = until.saturating_duration_since(Instant::now());
+        if left.is_zero() {
+            return true;
+        }
+        thread::sleep(left.min(POLL));
+    }
+    false
+}
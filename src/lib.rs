@@ -1,2 +1,5 @@
 pub mod cmd;
 pub mod path;
+
+pub use path::listing::{list_directory, render};
+pub use path::options::Options;
@@ -1,20 +1,429 @@
 use clap::Parser;
-use lsr::cmd::cli::Cli;
+use lsr::cmd::progress::Progress;
+use lsr::cmd::status::{report, Status};
+use lsr::cmd::watch;
+use lsr::cmd::{args, cli::Cli};
+use lsr::path::listing::{self, describe};
 use lsr::path::paths::{Path, Paths};
-use std::{env, fs};
+use lsr::path::usage::{self, Usage};
+use lsr::path::walk::{Visit, Walk};
+use lsr::path::{archive, diff::Snapshot, options::Output, record, tree};
+use lsr::{list_directory, Options};
+use std::{
+    fs,
+    io::{self, IsTerminal, Write},
+    process::ExitCode,
+};
 
-fn main() -> std::io::Result<()> {
-    let cli = Cli::parse();
-    let mut paths = Paths::default();
-    paths.setup_args((cli.all, cli.long, cli.tree));
+fn main() -> ExitCode {
+    let cli = match Cli::try_parse() {
+        Ok(cli) => cli,
+        Err(err) => {
+            // Usage errors go to stderr and are serious; --help and --version
+            // go to stdout and are not
+            let _ = err.print();
+            return if err.use_stderr() {
+                Status::Serious.into()
+            } else {
+                Status::Success.into()
+            };
+        }
+    };
+    let options = cli.options();
+    options.color.apply();
+    if cli.explain {
+        // On stderr, so the listing itself stays the same
+        for line in options.explain() {
+            eprintln!("{line}");
+        }
+    }
+    if let Some(root) = &options.tree {
+        return tree(root, &options, &cli).into();
+    }
+    let mut status = Status::Success;
+    let targets = if cli.paths.is_empty() {
+        vec![".".to_string()]
+    } else {
+        let mut targets = Vec::new();
+        for target in &cli.paths {
+            let expanded = args::expand(target);
+            if expanded.is_empty() {
+                report(cli.quiet, format!("no matches for '{target}'"));
+                status = Status::Serious;
+            }
+            targets.extend(expanded);
+        }
+        targets
+    };
+    if cli.summarize {
+        return status.max(summarize(&targets, &options, cli.quiet)).into();
+    }
+
+    // Files named explicitly are always shown, even if they start with a dot
+    let mut files = Paths::with_options(Options {
+        all: true,
+        ..options.clone()
+    });
+    let mut dirs = Vec::new();
+    let is_archive = |path: &std::path::Path| cli.archives && archive::is_archive(path);
+    // Like ls, symlinks named on the command line are followed unless the long
+    // format would rather show the link itself; links found inside a listed
+    // directory never are
+    let follow = cli.dereference_command_line || !options.long;
+    for target in &targets {
+        let path = std::path::Path::new(target);
+        // A dangling symlink can't be followed but is still listed as itself
+        let metadata = if follow {
+            fs::metadata(path).or_else(|_| fs::symlink_metadata(path))
+        } else {
+            fs::symlink_metadata(path)
+        };
+        match metadata {
+            Ok(metadata) if metadata.is_dir() && !cli.no_expand => dirs.push(target),
+            Ok(_) if is_archive(path) => dirs.push(target),
+            Ok(_) => match Path::from_file(path, follow) {
+                Ok(file) => files.paths.push(file),
+                Err(err) => {
+                    report(
+                        cli.quiet,
+                        format!("cannot access '{target}': {}", describe(&err)),
+                    );
+                    status = status.max(Status::Serious);
+                }
+            },
+            Err(err) => {
+                report(
+                    cli.quiet,
+                    format!("cannot access '{target}': {}", describe(&err)),
+                );
+                status = status.max(Status::Serious);
+            }
+        }
+    }
+
+    if cli.watch {
+        return status.max(watch(&dirs, &options, &cli)).into();
+    }
+
+    // Headers would break structured output, so only text gets them
+    let show_headers = (targets.len() > 1 || options.recursive) && options.output == Output::Text;
+    let mut first = files.paths.is_empty();
+    if !first {
+        if let Err(stop) = print(None, files) {
+            return status.max(stop).into();
+        }
+    }
+    if cli.merge {
+        status = status.max(merge(&dirs, !first, &options, &cli));
+        return status.max(group_sizes(&dirs, &options, &cli)).into();
+    }
+    // A recursive listing streams out as it goes, which is feedback enough on
+    // a terminal; the spinner is for when it is going to a file or a pipe
+    let mut progress =
+        Progress::new(options.recursive && !cli.no_progress && !io::stdout().is_terminal());
+    for dir in &dirs {
+        let path = std::path::Path::new(dir);
+        if options.output == Output::JsonLines && !options.recursive && !is_archive(path) {
+            match stream(path, &options, cli.quiet) {
+                Ok(streamed) => status = status.max(streamed),
+                Err(stop) => return status.max(stop).into(),
+            }
+            continue;
+        }
+        let visits: Box<dyn Iterator<Item = Visit>> = if options.recursive && !is_archive(path) {
+            Box::new(Walk::new(path, &options))
+        } else {
+            let listing = if is_archive(path) {
+                archive::list_archive(path, &options)
+            } else {
+                list_directory(path, &options)
+            };
+            Box::new(std::iter::once(Visit {
+                path: path.into(),
+                depth: 0,
+                listing,
+            }))
+        };
+        for visit in visits {
+            let name = options.shown_path(&visit.path);
+            match visit.listing {
+                Ok(mut paths) => {
+                    progress.add(paths.paths.len());
+                    let header = show_headers.then(|| match cli.indent {
+                        // Nested under its parent's entries, with no gap
+                        Some(indent) => {
+                            paths.options.margin = (visit.depth + 1) * indent;
+                            format!("{}{name}:\n", " ".repeat(visit.depth * indent))
+                        }
+                        None => {
+                            let gap = if first { "" } else { "\n" };
+                            format!("{gap}{name}:\n")
+                        }
+                    });
+                    first = false;
+                    for error in paths.errors.drain(..) {
+                        report(cli.quiet, error);
+                        status = status.max(Status::Minor);
+                    }
+                    if let Err(stop) = print(header, paths) {
+                        return status.max(stop).into();
+                    }
+                }
+                Err(err) => {
+                    report(
+                        cli.quiet,
+                        format!("cannot open directory '{name}': {}", describe(&err)),
+                    );
+                    // Only an argument that can't be opened is serious, like ls
+                    let failure = if visit.depth == 0 {
+                        Status::Serious
+                    } else {
+                        Status::Minor
+                    };
+                    status = status.max(failure);
+                }
+            }
+        }
+    }
+
+    status.max(group_sizes(&dirs, &options, &cli)).into()
+}
 
-    let path = env::current_dir()?;
-    let contents = fs::read_dir(&path)?;
+/// The `--group-sizes` footer under everything listed, if it was asked for.
+fn group_sizes(dirs: &[&String], options: &Options, cli: &Cli) -> Status {
+    if !cli.group_sizes || dirs.is_empty() || options.output != Output::Text {
+        return Status::Success;
+    }
+    let dirs: Vec<_> = dirs.iter().map(std::path::Path::new).collect();
+    let footer = usage::groups(&dirs, options);
+    match written(writeln!(io::stdout().lock(), "\n{footer}")) {
+        Ok(()) => Status::Success,
+        Err(stop) => stop,
+    }
+}
 
-    for content in contents {
-        paths.paths.push(Path::new(content?));
+/// Writes `dir` as JSON lines as its entries are read, for `--jsonl`. An
+/// `Err` means output can't go on, like with `print`.
+fn stream(dir: &std::path::Path, options: &Options, quiet: bool) -> Result<Status, Status> {
+    let lines = match listing::json_lines(dir, options) {
+        Ok(lines) => lines,
+        Err(err) => {
+            report(
+                quiet,
+                format!(
+                    "cannot open directory '{}': {}",
+                    dir.display(),
+                    describe(&err)
+                ),
+            );
+            return Ok(Status::Serious);
+        }
+    };
+    let mut status = Status::Success;
+    let mut out = io::stdout().lock();
+    for (line, error) in lines {
+        if let Some(line) = line {
+            if let Err(stop) = written(out.write_all(line.as_bytes())) {
+                return Err(status.max(stop));
+            }
+        }
+        if let Some(error) = error {
+            report(quiet, error);
+            status = status.max(Status::Minor);
+        }
     }
+    Ok(status)
+}
 
-    paths.print();
-    Ok(())
+/// Draws the tree below `root`, under a line naming it like `tree` does.
+fn tree(root: &str, options: &Options, cli: &Cli) -> Status {
+    let quiet = cli.quiet;
+    if options.output == Output::Json {
+        return json_tree(root, options, quiet);
+    }
+    let mut progress = Progress::new(!cli.no_progress);
+    let listing = tree::list_tree_with(std::path::Path::new(root), options, &mut |entries| {
+        progress.add(entries)
+    });
+    progress.clear();
+    let mut paths = match listing {
+        Ok(paths) => paths,
+        Err(err) => {
+            report(
+                quiet,
+                format!("cannot open directory '{root}': {}", describe(&err)),
+            );
+            return Status::Serious;
+        }
+    };
+    let mut status = Status::Success;
+    for error in paths.errors.drain(..) {
+        report(quiet, error);
+        status = Status::Minor;
+    }
+    let header = (options.output == Output::Text).then(|| {
+        let root = options.shown_path(std::path::Path::new(root));
+        format!("{root}\n")
+    });
+    match print(header, paths) {
+        Ok(()) => status,
+        Err(stop) => status.max(stop),
+    }
+}
+
+/// Lists `dirs` again and again, every `--interval`, each time on a clean
+/// screen when on a terminal, until Ctrl-C. With `--diff`, what changed
+/// since the last refresh is marked, and entries that are gone show once.
+fn watch(dirs: &[&String], options: &Options, cli: &Cli) -> Status {
+    watch::catch_interrupt();
+    let mut status = Status::Success;
+    let mut snapshots: Vec<Option<Snapshot>> = dirs.iter().map(|_| None).collect();
+    let clear = io::stdout().is_terminal();
+    loop {
+        // Drawn whole and written at once, so the screen doesn't flicker
+        let mut screen = String::from(if clear { "\x1b[2J\x1b[H" } else { "" });
+        for (i, (dir, snapshot)) in dirs.iter().zip(snapshots.iter_mut()).enumerate() {
+            let mut paths = match list_directory(std::path::Path::new(dir), options) {
+                Ok(paths) => paths,
+                Err(err) => {
+                    report(
+                        cli.quiet,
+                        format!("cannot open directory '{dir}': {}", describe(&err)),
+                    );
+                    status = status.max(Status::Serious);
+                    continue;
+                }
+            };
+            for error in paths.errors.drain(..) {
+                report(cli.quiet, error);
+                status = status.max(Status::Minor);
+            }
+            if cli.diff {
+                let current = Snapshot::of(&paths);
+                if let Some(previous) = snapshot.replace(current) {
+                    previous.compare(&mut paths);
+                }
+            }
+            if dirs.len() > 1 {
+                let gap = if i == 0 { "" } else { "\n" };
+                screen.push_str(&format!("{gap}{}:\n", options.shown_path(dir.as_ref())));
+            }
+            screen.push_str(&paths.render());
+        }
+        let mut out = io::stdout().lock();
+        if let Err(stop) = written(out.write_all(screen.as_bytes()).and_then(|()| out.flush())) {
+            return status.max(stop);
+        }
+        drop(out);
+        if !watch::wait(cli.interval) {
+            return status;
+        }
+    }
+}
+
+/// The tree below `root` as one nested JSON object, for `--tree --json`.
+fn json_tree(root: &str, options: &Options, quiet: bool) -> Status {
+    let (node, errors) = match tree::tree_nodes(std::path::Path::new(root), options) {
+        Ok(tree) => tree,
+        Err(err) => {
+            report(
+                quiet,
+                format!("cannot open directory '{root}': {}", describe(&err)),
+            );
+            return Status::Serious;
+        }
+    };
+    let mut status = Status::Success;
+    for error in errors {
+        report(quiet, error);
+        status = Status::Minor;
+    }
+    let json = record::to_json_tree(&node);
+    match written(io::stdout().lock().write_all(json.as_bytes())) {
+        Ok(()) => status,
+        Err(stop) => status.max(stop),
+    }
+}
+
+/// One listing of everything in `dirs`, set apart from any files listed
+/// before it.
+fn merge(dirs: &[&String], after_files: bool, options: &Options, cli: &Cli) -> Status {
+    let mut status = Status::Success;
+    let mut listings = Vec::new();
+    for dir in dirs {
+        let path = std::path::Path::new(dir);
+        let listing = if cli.archives && archive::is_archive(path) {
+            archive::list_archive(path, options)
+        } else {
+            list_directory(path, options)
+        };
+        match listing {
+            Ok(mut paths) => {
+                for error in paths.errors.drain(..) {
+                    report(cli.quiet, error);
+                    status = status.max(Status::Minor);
+                }
+                listings.push((dir.to_string(), paths));
+            }
+            Err(err) => {
+                report(
+                    cli.quiet,
+                    format!("cannot open directory '{dir}': {}", describe(&err)),
+                );
+                status = status.max(Status::Serious);
+            }
+        }
+    }
+    let header = (after_files && options.output == Output::Text).then(|| "\n".into());
+    match print(header, Paths::merge(options.clone(), listings)) {
+        Ok(()) => status,
+        Err(stop) => status.max(stop),
+    }
+}
+
+/// One line per target with its total size, like `du -s`.
+fn summarize(targets: &[String], options: &Options, quiet: bool) -> Status {
+    let mut status = Status::Success;
+    let mut usage = Usage::new(options.actual_size);
+    for target in targets {
+        match usage::summary(&mut usage, std::path::Path::new(target), options) {
+            Ok(line) => {
+                if let Err(stop) = written(writeln!(io::stdout().lock(), "{line}")) {
+                    return status.max(stop);
+                }
+            }
+            Err(err) => {
+                report(
+                    quiet,
+                    format!("cannot access '{target}': {}", describe(&err)),
+                );
+                status = status.max(Status::Serious);
+            }
+        }
+    }
+    status
+}
+
+/// Writes `paths` under an optional header. An `Err` means output can't go
+/// on and carries the status to exit with.
+fn print(header: Option<String>, paths: Paths) -> Result<(), Status> {
+    let mut out = io::stdout().lock();
+    let header = match header {
+        Some(header) => out.write_all(header.as_bytes()),
+        None => Ok(()),
+    };
+    written(header.and_then(|()| paths.write_to(&mut out)))
+}
+
+/// What a write to stdout means for the run.
+fn written(result: io::Result<()>) -> Result<(), Status> {
+    match result {
+        Ok(()) => Ok(()),
+        // Stop quietly when the reader goes away, e.g. `lsr | head`
+        Err(err) if err.kind() == io::ErrorKind::BrokenPipe => Err(Status::Success),
+        Err(err) => {
+            report(false, format!("write error: {}", describe(&err)));
+            Err(Status::Serious)
+        }
+    }
 }
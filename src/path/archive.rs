@@ -0,0 +1,184 @@
+use super::{
+    options::Options,
+    paths::{Path, Paths},
+};
+use chrono::{Local, NaiveDate, TimeZone};
+use flate2::read::GzDecoder;
+use std::{
+    fs::File,
+    io::{self, BufReader, Read},
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+/// Whether `path` looks like an archive `lsr` can list, going by extension.
+pub fn is_archive(path: &std::path::Path) -> bool {
+    let name = path.to_string_lossy().to_lowercase();
+    [".zip", ".jar", ".tar", ".tar.gz", ".tgz"]
+        .iter()
+        .any(|extension| name.ends_with(extension))
+}
+
+/// Lists the entries inside a zip or tar archive as if it were a directory.
+/// Sizes are the uncompressed lengths. Each entry's path is rooted at the
+/// archive, e.g. `x.zip/src/`, and options that would stat the entries are
+/// left off.
+pub fn list_archive(path: &std::path::Path, options: &Options) -> io::Result<Paths> {
+    // The entries aren't on disk, so nothing may stat or read them by path
+    let mut paths = Paths::with_options(Options {
+        dir_sizes: false,
+        child_count: false,
+        mark_empty: false,
+        dedup: None,
+        xattrs: false,
+        ..options.clone()
+    });
+    let name = path.to_string_lossy().to_lowercase();
+    let file = BufReader::new(File::open(path)?);
+    paths.paths = if name.ends_with(".zip") || name.ends_with(".jar") {
+        zip_entries(path, file)?
+    } else if name.ends_with(".gz") || name.ends_with(".tgz") {
+        tar_entries(path, GzDecoder::new(file))?
+    } else {
+        tar_entries(path, file)?
+    };
+    Ok(paths)
+}
+
+fn zip_entries(path: &std::path::Path, file: BufReader<File>) -> io::Result<Vec<Path>> {
+    let mut archive = zip::ZipArchive::new(file).map_err(io::Error::other)?;
+    let mut entries = Vec::with_capacity(archive.len());
+    for index in 0..archive.len() {
+        let entry = archive.by_index_raw(index).map_err(io::Error::other)?;
+        // Zip timestamps carry no timezone and are written in local time
+        let modified = entry.last_modified().and_then(|time| {
+            NaiveDate::from_ymd_opt(time.year().into(), time.month().into(), time.day().into())?
+                .and_hms_opt(
+                    time.hour().into(),
+                    time.minute().into(),
+                    time.second().into(),
+                )
+                .and_then(|naive| Local.from_local_datetime(&naive).single())
+                .map(SystemTime::from)
+        });
+        entries.push(Path::from_archive_entry(
+            path,
+            entry.name(),
+            entry.size(),
+            entry.unix_mode(),
+            modified,
+        ));
+    }
+    Ok(entries)
+}
+
+fn tar_entries(path: &std::path::Path, reader: impl Read) -> io::Result<Vec<Path>> {
+    let mut archive = tar::Archive::new(reader);
+    let mut entries = Vec::new();
+    for entry in archive.entries()? {
+        let entry = entry?;
+        let header = entry.header();
+        let mut name = entry.path()?.to_string_lossy().into_owned();
+        if header.entry_type().is_dir() && !name.ends_with('/') {
+            name.push('/');
+        }
+        let modified = header
+            .mtime()
+            .ok()
+            .map(|seconds| UNIX_EPOCH + Duration::from_secs(seconds));
+        entries.push(Path::from_archive_entry(
+            path,
+            &name,
+            entry.size(),
+            header.mode().ok(),
+            modified,
+        ));
+    }
+    Ok(entries)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::path::layout::plain;
+    use std::io::Write;
+
+    fn names(paths: &Paths) -> Vec<String> {
+        plain(&paths.render())
+            .lines()
+            .map(|line| line.trim().to_string())
+            .collect()
+    }
+
+    #[test]
+    fn is_archive_should_check_extension() {
+        assert!(is_archive(std::path::Path::new("a.ZIP")));
+        assert!(is_archive(std::path::Path::new("a.tar.gz")));
+        assert!(!is_archive(std::path::Path::new("a.txt")));
+    }
+
+    #[test]
+    fn zip_entries_should_be_listed() {
+        let path = std::env::temp_dir().join("lsr_archive_test.zip");
+        let mut writer = zip::ZipWriter::new(File::create(&path).unwrap());
+        let options = zip::write::SimpleFileOptions::default()
+            .compression_method(zip::CompressionMethod::Deflated);
+        writer.add_directory("docs/", options).unwrap();
+        writer.start_file("docs/readme.txt", options).unwrap();
+        writer.write_all(&[b'a'; 2000]).unwrap();
+        writer.finish().unwrap();
+
+        let paths = list_archive(&path, &Options::default()).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(names(&paths), vec!["docs", "docs/readme.txt"]);
+        let records: Vec<_> = paths.paths.iter().map(|path| path.record()).collect();
+        assert_eq!(records[0].kind, "directory");
+        assert_eq!(records[1].size, Some(2000));
+    }
+
+    #[test]
+    fn archive_entries_should_not_be_statted() {
+        // Named like a directory of the crate, which a relative path would hit
+        let path = std::env::temp_dir().join("lsr_archive_stat.zip");
+        let mut writer = zip::ZipWriter::new(File::create(&path).unwrap());
+        let options = zip::write::SimpleFileOptions::default();
+        writer.add_directory("src/", options).unwrap();
+        writer.finish().unwrap();
+
+        let options = Options {
+            child_count: true,
+            mark_empty: true,
+            dir_sizes: true,
+            ..Default::default()
+        };
+        let paths = list_archive(&path, &options).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(names(&paths), vec!["src"]);
+        assert_eq!(paths.paths[0].full_path(), path.join("src/"));
+    }
+
+    #[test]
+    fn tar_entries_should_be_listed() {
+        let path = std::env::temp_dir().join("lsr_archive_test.tar");
+        let mut builder = tar::Builder::new(File::create(&path).unwrap());
+        let mut header = tar::Header::new_gnu();
+        header.set_size(5);
+        header.set_mode(0o644);
+        header.set_mtime(1675111074);
+        header.set_cksum();
+        builder
+            .append_data(&mut header, "notes.txt", &b"hello"[..])
+            .unwrap();
+        builder.finish().unwrap();
+        drop(builder);
+
+        let paths = list_archive(&path, &Options::default()).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        let record = paths.paths[0].record();
+        assert_eq!(record.name, "notes.txt");
+        assert_eq!(record.size, Some(5));
+        assert!(record.modified.is_some());
+    }
+}
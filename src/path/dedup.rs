@@ -0,0 +1,129 @@
+use super::{usage, vfs};
+use std::{
+    collections::{hash_map::DefaultHasher, HashMap, HashSet},
+    fs::{self, File},
+    hash::Hasher,
+    io::{self, BufReader, Read},
+    path::{Path, PathBuf},
+};
+
+/// Bytes read from a file at a time while hashing or comparing it.
+const CHUNK: usize = 64 * 1024;
+
+/// Tells which files hold the same bytes as one seen before, for `--dedup`.
+/// Files are hashed to find candidates, and a matching hash is confirmed by
+/// comparing the contents, so a collision can't hide a file. A hard link to
+/// a file already seen is a copy of it without being read again.
+#[derive(Debug, Default)]
+pub struct Copies {
+    /// Files seen so far by length and content hash. Files that only share
+    /// the hash each get their own place.
+    seen: HashMap<(u64, u64), Vec<PathBuf>>,
+    inodes: HashSet<(u64, u64)>,
+}
+
+impl Copies {
+    /// Whether `path` holds the same bytes as a file passed in before. Only
+    /// regular files can be copies; symlinks aren't followed, and files that
+    /// can't be read, or would better not be, like those in `/proc`, never
+    /// match.
+    pub fn is_copy(&mut self, path: &Path) -> bool {
+        let Ok(metadata) = fs::symlink_metadata(path) else {
+            return false;
+        };
+        if !metadata.is_file() || vfs::is_virtual(path) {
+            return false;
+        }
+        let identity = usage::identity(&metadata);
+        if identity.is_some_and(|identity| self.inodes.contains(&identity)) {
+            return true;
+        }
+        let Ok(hash) = hash(path) else {
+            return false;
+        };
+        self.inodes.extend(identity);
+        let same = self.seen.entry((metadata.len(), hash)).or_default();
+        if same
+            .iter()
+            .any(|earlier| same_content(earlier, path).unwrap_or(false))
+        {
+            return true;
+        }
+        same.push(path.to_path_buf());
+        false
+    }
+}
+
+fn hash(path: &Path) -> io::Result<u64> {
+    let mut file = File::open(path)?;
+    let mut hasher = DefaultHasher::new();
+    let mut buffer = vec![0; CHUNK];
+    loop {
+        match file.read(&mut buffer)? {
+            0 => return Ok(hasher.finish()),
+            read => hasher.write(&buffer[..read]),
+        }
+    }
+}
+
+/// Compares two files of the same length byte by byte.
+fn same_content(a: &Path, b: &Path) -> io::Result<bool> {
+    let (mut a, mut b) = (
+        BufReader::new(File::open(a)?),
+        BufReader::new(File::open(b)?),
+    );
+    let (mut chunk_a, mut chunk_b) = (vec![0; CHUNK], vec![0; CHUNK]);
+    loop {
+        let read = read_full(&mut a, &mut chunk_a)?;
+        if read != read_full(&mut b, &mut chunk_b)? || chunk_a[..read] != chunk_b[..read] {
+            return Ok(false);
+        }
+        if read == 0 {
+            return Ok(true);
+        }
+    }
+}
+
+/// Fills as much of `buffer` as the file has left, since one `read` may
+/// return less.
+fn read_full(file: &mut impl Read, buffer: &mut [u8]) -> io::Result<usize> {
+    let mut filled = 0;
+    while filled < buffer.len() {
+        match file.read(&mut buffer[filled..])? {
+            0 => break,
+            read => filled += read,
+        }
+    }
+    Ok(filled)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn only_later_copies_should_count() {
+        let dir = std::env::temp_dir().join("lsr_dedup");
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(dir.join("sub")).unwrap();
+        fs::write(dir.join("a"), "same").unwrap();
+        fs::write(dir.join("b"), "same").unwrap();
+        fs::write(dir.join("c"), "diff").unwrap();
+        fs::write(dir.join("empty"), "").unwrap();
+        #[cfg(unix)]
+        fs::hard_link(dir.join("c"), dir.join("d")).unwrap();
+
+        let mut copies = Copies::default();
+        let mut found: Vec<_> = ["a", "b", "c", "empty", "sub"]
+            .into_iter()
+            .map(|name| copies.is_copy(&dir.join(name)))
+            .collect();
+        #[cfg(unix)]
+        found.push(copies.is_copy(&dir.join("d")));
+        fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(found[..5], [false, true, false, false, false]);
+        #[cfg(unix)]
+        assert!(found[5]);
+    }
+}
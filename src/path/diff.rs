@@ -0,0 +1,103 @@
+use super::paths::{Path, Paths};
+use std::collections::HashMap;
+
+/// How an entry changed since the previous refresh of `--watch --diff`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Change {
+    Added,
+    /// Its size or modification time is not what it was.
+    Modified,
+    /// Gone since the last refresh. It is still shown this once.
+    Removed,
+}
+
+/// The entries of one refresh by name, to tell the next one what changed.
+#[derive(Debug, Default)]
+pub struct Snapshot {
+    entries: HashMap<String, Path>,
+}
+
+impl Snapshot {
+    /// Remembers `paths` as they were read, before a listing formats them.
+    pub fn of(paths: &Paths) -> Self {
+        let entries = paths
+            .paths
+            .iter()
+            .filter(|path| path.change() != Some(Change::Removed))
+            .map(|path| (path.name().to_string(), path.clone()))
+            .collect();
+        Snapshot { entries }
+    }
+
+    /// Marks each entry of `paths` that is new or changed since this
+    /// snapshot, and adds back the ones that are gone, marked as removed.
+    pub fn compare(&self, paths: &mut Paths) {
+        for path in paths.paths.iter_mut() {
+            match self.entries.get(path.name()) {
+                None => path.set_change(Change::Added),
+                Some(earlier) if path.differs_from(earlier) => path.set_change(Change::Modified),
+                Some(_) => {}
+            }
+        }
+        let mut removed: Vec<Path> = self
+            .entries
+            .iter()
+            .filter(|(name, _)| !paths.paths.iter().any(|path| path.name() == *name))
+            .map(|(_, earlier)| {
+                let mut gone = earlier.clone();
+                gone.set_change(Change::Removed);
+                gone
+            })
+            .collect();
+        paths.paths.append(&mut removed);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::path::{listing::list_directory, options::Options};
+    use std::fs;
+
+    #[test]
+    fn compare_should_mark_added_modified_and_removed() {
+        let dir = std::env::temp_dir().join("lsr_diff");
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join("kept"), "same").unwrap();
+        fs::write(dir.join("grown"), "a").unwrap();
+        fs::write(dir.join("deleted"), "").unwrap();
+        let options = Options {
+            watch_diff: true,
+            ..Default::default()
+        };
+        let read = || list_directory(&dir, &options).unwrap();
+
+        let before = Snapshot::of(&read());
+        fs::write(dir.join("grown"), "abc").unwrap();
+        fs::remove_file(dir.join("deleted")).unwrap();
+        fs::write(dir.join("new"), "").unwrap();
+        let mut after = read();
+        before.compare(&mut after);
+        let again = Snapshot::of(&after);
+        fs::remove_dir_all(&dir).unwrap();
+
+        let mut changes: Vec<_> = after
+            .paths
+            .iter()
+            .map(|path| (path.name().to_string(), path.change()))
+            .collect();
+        changes.sort_by(|a, b| a.0.cmp(&b.0));
+        assert_eq!(
+            changes,
+            [
+                ("deleted".to_string(), Some(Change::Removed)),
+                ("grown".to_string(), Some(Change::Modified)),
+                ("kept".to_string(), None),
+                ("new".to_string(), Some(Change::Added)),
+            ]
+        );
+        // Removed entries only linger for the one refresh
+        assert!(!again.entries.contains_key("deleted"));
+    }
+}
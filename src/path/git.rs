@@ -0,0 +1,125 @@
+use std::{
+    collections::HashSet,
+    io,
+    path::{Component, Path},
+    process::Command,
+};
+
+/// Names of the entries of `dir` that changed since `reference`, as far as
+/// `git diff` sees it: files that differ from the commit, and directories
+/// holding any. Untracked files are not part of the diff, so they never
+/// count as changed.
+pub fn changed_since(dir: &Path, reference: &str) -> io::Result<HashSet<String>> {
+    git(dir, &["rev-parse", "--show-toplevel"])
+        .map_err(|_| io::Error::other("not a git repository"))?;
+    git(
+        dir,
+        &[
+            "rev-parse",
+            "--verify",
+            "--quiet",
+            &format!("{reference}^{{commit}}"),
+        ],
+    )
+    .map_err(|_| io::Error::other(format!("unknown git revision '{reference}'")))?;
+    let diff = git(
+        dir,
+        &["diff", "--name-only", "--relative", "-z", reference, "--"],
+    )?;
+    Ok(diff
+        .split(|&byte| byte == 0)
+        .filter_map(|path| {
+            let path = Path::new(std::str::from_utf8(path).ok()?);
+            match path.components().next()? {
+                Component::Normal(name) => Some(name.to_string_lossy().into_owned()),
+                _ => None,
+            }
+        })
+        .collect())
+}
+
+/// Runs git in `dir` and returns what it printed, or its first line of
+/// complaint when it fails.
+fn git(dir: &Path, args: &[&str]) -> io::Result<Vec<u8>> {
+    let output = Command::new("git")
+        .arg("-C")
+        .arg(dir)
+        .args(args)
+        .output()
+        .map_err(|error| io::Error::new(error.kind(), format!("cannot run git: {error}")))?;
+    if output.status.success() {
+        return Ok(output.stdout);
+    }
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    let message = stderr.lines().next().unwrap_or("git failed");
+    Err(io::Error::other(
+        message
+            .strip_prefix("fatal: ")
+            .unwrap_or(message)
+            .to_string(),
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    fn run(dir: &Path, args: &[&str]) {
+        let status = Command::new("git")
+            .arg("-C")
+            .arg(dir)
+            .args(["-c", "user.name=lsr", "-c", "user.email=lsr@example.com"])
+            .args(args)
+            .output()
+            .unwrap()
+            .status;
+        assert!(status.success(), "git {args:?}");
+    }
+
+    /// A repository with one commit, then changes to `changed` and
+    /// `sub/inner` after it. `None` when git is not installed.
+    fn repository(name: &str) -> Option<std::path::PathBuf> {
+        Command::new("git").arg("--version").output().ok()?;
+        let root = std::env::temp_dir().join(name);
+        let _ = fs::remove_dir_all(&root);
+        fs::create_dir_all(root.join("sub")).unwrap();
+        for file in ["changed", "same", "sub/inner"] {
+            fs::write(root.join(file), "before").unwrap();
+        }
+        run(&root, &["init", "-q"]);
+        run(&root, &["add", "."]);
+        run(&root, &["commit", "-q", "-m", "first"]);
+        fs::write(root.join("changed"), "after").unwrap();
+        fs::write(root.join("sub").join("inner"), "after").unwrap();
+        fs::write(root.join("untracked"), "").unwrap();
+        Some(root)
+    }
+
+    #[test]
+    fn changes_should_be_named_by_their_entry() {
+        let Some(root) = repository("lsr_git_changed") else {
+            return;
+        };
+
+        let changed = changed_since(&root, "HEAD").unwrap();
+        let below = changed_since(&root.join("sub"), "HEAD").unwrap();
+        let bad = changed_since(&root, "no-such-ref").unwrap_err();
+        fs::remove_dir_all(&root).unwrap();
+
+        assert_eq!(changed, HashSet::from(["changed".into(), "sub".into()]));
+        assert_eq!(below, HashSet::from(["inner".into()]));
+        assert_eq!(bad.to_string(), "unknown git revision 'no-such-ref'");
+    }
+
+    #[test]
+    fn outside_a_repository_should_say_so() {
+        let dir = std::env::temp_dir().join("lsr_git_none");
+        fs::create_dir_all(&dir).unwrap();
+
+        let error = changed_since(&dir, "HEAD").unwrap_err();
+        fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(error.to_string(), "not a git repository");
+    }
+}
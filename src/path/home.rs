@@ -0,0 +1,43 @@
+use std::path::Path;
+
+/// `path` with the home directory at its start written as `~`, the way
+/// shells abbreviate it, e.g. `~/src/lsr`. Paths outside it, and every path
+/// when `$HOME` isn't set, are written out in full.
+pub fn collapse(path: &Path) -> String {
+    let home = std::env::var_os("HOME").filter(|home| !home.is_empty());
+    collapse_under(path, home.as_deref().map(Path::new))
+}
+
+fn collapse_under(path: &Path, home: Option<&Path>) -> String {
+    // Only whole components match, so /home/ann doesn't take /home/anna
+    match home.and_then(|home| path.strip_prefix(home).ok()) {
+        Some(rest) if rest.as_os_str().is_empty() => "~".to_string(),
+        Some(rest) => format!("~/{}", rest.display()),
+        None => path.display().to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn home_should_become_a_tilde() {
+        let home = Some(Path::new("/home/ann"));
+
+        assert_eq!(collapse_under(Path::new("/home/ann"), home), "~");
+        assert_eq!(
+            collapse_under(Path::new("/home/ann/src/lsr"), home),
+            "~/src/lsr"
+        );
+        assert_eq!(
+            collapse_under(Path::new("/home/anna/src"), home),
+            "/home/anna/src"
+        );
+        assert_eq!(collapse_under(Path::new("relative"), home), "relative");
+        assert_eq!(
+            collapse_under(Path::new("/home/ann/x"), None),
+            "/home/ann/x"
+        );
+    }
+}
@@ -0,0 +1,422 @@
+use std::io::IsTerminal;
+use unicode_width::{UnicodeWidthChar, UnicodeWidthStr};
+
+/// Columns `text` takes up on a terminal: wide characters count twice and
+/// combining marks not at all, unlike its length in bytes.
+pub fn width(text: &str) -> usize {
+    text.width()
+}
+
+/// `text` cut down to at most `max` columns, ending in `…` when anything
+/// had to go. Whole characters are dropped, so a wide one that doesn't fit
+/// leaves the result a column short instead of being split.
+pub fn truncate(text: &str, max: usize) -> String {
+    if width(text) <= max {
+        return text.to_string();
+    }
+    let mut kept = String::new();
+    let mut kept_width = 0;
+    for c in text.chars() {
+        let c_width = c.width().unwrap_or(0);
+        // One column stays free for the ellipsis
+        if kept_width + c_width + 1 > max {
+            break;
+        }
+        kept.push(c);
+        kept_width += c_width;
+    }
+    if max > 0 {
+        kept.push('…');
+    }
+    kept
+}
+
+/// `text` cut down to at most `max` bytes, ending in `…` when anything had
+/// to go, which counts towards `max` too. The cut falls between characters.
+pub fn truncate_bytes(text: &str, max: usize) -> String {
+    if text.len() <= max {
+        return text.to_string();
+    }
+    let ellipsis = '…'.len_utf8();
+    let Some(room) = max.checked_sub(ellipsis) else {
+        return String::new();
+    };
+    let end = (0..=room)
+        .rev()
+        .find(|&end| text.is_char_boundary(end))
+        .unwrap_or(0);
+    format!("{}…", &text[..end])
+}
+
+/// Width to lay the output out in. A positive `COLUMNS` wins, even when
+/// stdout is piped or redirected; otherwise the terminal attached to stdout
+/// is asked, falling back to 80. `None` when there is neither, so piped
+/// output isn't wrapped. `--output-width` is applied on top by the caller.
+pub fn terminal_width() -> Option<usize> {
+    pick_width(
+        std::env::var("COLUMNS").ok().as_deref(),
+        std::io::stdout().is_terminal(),
+        query_width,
+    )
+}
+
+/// A positive `COLUMNS`, else on a terminal the queried width or 80.
+/// Anything else in `COLUMNS` is ignored like an unset variable.
+fn pick_width(
+    columns: Option<&str>,
+    terminal: bool,
+    query: impl FnOnce() -> Option<usize>,
+) -> Option<usize> {
+    let columns = columns
+        .and_then(|columns| columns.trim().parse().ok())
+        .filter(|&columns| columns > 0);
+    if columns.is_some() || !terminal {
+        return columns;
+    }
+    Some(query().unwrap_or(80))
+}
+
+#[cfg(unix)]
+fn query_width() -> Option<usize> {
+    // SAFETY: TIOCGWINSZ only writes into the winsize struct we hand it.
+    unsafe {
+        let mut size: libc::winsize = std::mem::zeroed();
+        if libc::ioctl(libc::STDOUT_FILENO, libc::TIOCGWINSZ, &mut size) == 0 && size.ws_col > 0 {
+            Some(size.ws_col as usize)
+        } else {
+            None
+        }
+    }
+}
+
+#[cfg(not(unix))]
+fn query_width() -> Option<usize> {
+    None
+}
+
+/// Lays `cells` out column by column in as many columns as fit in `width`,
+/// or in exactly `forced` columns however wide that makes the lines. Each
+/// cell is the text to print and its display width, which may differ from
+/// the text length when it carries color escapes.
+///
+/// Like GNU `ls`, each column is only as wide as its widest cell (or `min`)
+/// and `gap` spaces before the next one, so a few long names don't spread
+/// the short ones apart; the layout with the fewest rows whose lines fit is
+/// the one used. `forced` columns are filled as evenly as they can be, the
+/// first ones taking a cell more when the cells don't divide evenly, so
+/// that none is left empty unless there are fewer cells than columns.
+/// Cells are padded only when another follows them on their line.
+pub fn grid(
+    cells: &[(&str, usize)],
+    width: Option<usize>,
+    forced: Option<usize>,
+    gap: usize,
+    min: usize,
+) -> Vec<String> {
+    if width.is_none() && forced.is_none() {
+        return cells.iter().map(|(text, _)| text.to_string()).collect();
+    }
+    if cells.is_empty() {
+        return Vec::new();
+    }
+
+    let columns: Vec<&[(&str, usize)]> = match (forced, width) {
+        (Some(columns), _) => spread(cells, columns.clamp(1, cells.len())),
+        (None, Some(width)) => {
+            let rows = (1..cells.len())
+                .find(|&rows| {
+                    let columns: Vec<_> = cells.chunks(rows).collect();
+                    let widths = column_widths(&columns, min);
+                    widths.iter().sum::<usize>() + gap * (widths.len() - 1) <= width
+                })
+                .unwrap_or(cells.len());
+            cells.chunks(rows).collect()
+        }
+        (None, None) => unreachable!("handled above"),
+    };
+    let widths = column_widths(&columns, min);
+    let rows = columns.first().map_or(0, |column| column.len());
+
+    (0..rows)
+        .map(|row| {
+            // Columns only ever get shorter, so the cells of a row are the
+            // first few columns'
+            let cells: Vec<_> = columns
+                .iter()
+                .filter_map(|column| column.get(row))
+                .collect();
+            let mut line = String::new();
+            for (i, ((text, w), column_width)) in cells.iter().zip(&widths).enumerate() {
+                line.push_str(text);
+                if i + 1 < cells.len() {
+                    line.push_str(&" ".repeat(column_width - w + gap));
+                }
+            }
+            line
+        })
+        .collect()
+}
+
+/// Splits `cells` into exactly `count` columns, in order, the first ones a
+/// cell longer than the rest when they don't divide evenly.
+fn spread<'a, 'c>(cells: &'c [(&'a str, usize)], count: usize) -> Vec<&'c [(&'a str, usize)]> {
+    let (rows, longer) = (cells.len() / count, cells.len() % count);
+    let mut rest = cells;
+    (0..count)
+        .map(|column| {
+            let (taken, left) = rest.split_at(rows + usize::from(column < longer));
+            rest = left;
+            taken
+        })
+        .collect()
+}
+
+/// How wide each column's widest cell is, or `min` if that is wider.
+fn column_widths(columns: &[&[(&str, usize)]], min: usize) -> Vec<usize> {
+    columns
+        .iter()
+        .map(|column| column.iter().map(|(_, w)| *w).max().unwrap_or(0).max(min))
+        .collect()
+}
+
+/// Fills lines up to `width` with as many cells as fit, like paragraphs of
+/// words, never breaking a cell in two. `separator` is appended to every
+/// cell but the last, and cells are set one space apart.
+pub fn fill(cells: &[(&str, usize)], width: usize, separator: &str) -> Vec<String> {
+    let mut lines = Vec::new();
+    let (mut line, mut line_width) = (String::new(), 0);
+    for (i, (text, w)) in cells.iter().enumerate() {
+        let separator = if i + 1 < cells.len() { separator } else { "" };
+        let cell_width = w + separator.len();
+        if line_width > 0 && line_width + 1 + cell_width > width {
+            lines.push(std::mem::take(&mut line));
+            line_width = 0;
+        }
+        if line_width > 0 {
+            line.push(' ');
+            line_width += 1;
+        }
+        line.push_str(text);
+        line.push_str(separator);
+        line_width += cell_width;
+    }
+    if !line.is_empty() {
+        lines.push(line);
+    }
+    lines
+}
+
+/// Drops color escapes, which other tests may have turned on.
+#[cfg(test)]
+pub fn plain(text: &str) -> String {
+    let mut out = String::new();
+    let mut chars = text.chars();
+    while let Some(c) = chars.next() {
+        if c == '\u{1b}' {
+            chars.by_ref().find(|&c| c == 'm');
+        } else {
+            out.push(c);
+        }
+    }
+    out
+}
+
+/// A directory of test fixtures under the system temp dir, removed again
+/// when dropped, even if an assert failed first. Its name is `name` with
+/// the process id and a counter after it, so no two tests ever share one.
+#[cfg(test)]
+pub struct Scratch(std::path::PathBuf);
+
+#[cfg(test)]
+impl Scratch {
+    /// Creates the directory with `entries` in it: a directory for each
+    /// that ends in `/`, else an empty file, with any parents they need.
+    pub fn new(name: &str, entries: &[&str]) -> Self {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+
+        static COUNT: AtomicUsize = AtomicUsize::new(0);
+        let count = COUNT.fetch_add(1, Ordering::Relaxed);
+        let root = std::env::temp_dir().join(format!("{name}_{}_{count}", std::process::id()));
+        let scratch = Scratch(root);
+        std::fs::create_dir_all(&scratch.0).unwrap();
+        for entry in entries {
+            let path = scratch.join(entry);
+            match entry.ends_with('/') {
+                true => std::fs::create_dir_all(&path).unwrap(),
+                false => {
+                    std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+                    std::fs::write(&path, "").unwrap();
+                }
+            }
+        }
+        scratch
+    }
+}
+
+#[cfg(test)]
+impl std::ops::Deref for Scratch {
+    type Target = std::path::Path;
+
+    fn deref(&self) -> &std::path::Path {
+        &self.0
+    }
+}
+
+#[cfg(test)]
+impl AsRef<std::path::Path> for Scratch {
+    fn as_ref(&self) -> &std::path::Path {
+        &self.0
+    }
+}
+
+#[cfg(test)]
+impl Drop for Scratch {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.0);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn columns_should_win_over_the_terminal_and_80() {
+        assert_eq!(pick_width(Some("132"), true, || Some(100)), Some(132));
+        assert_eq!(pick_width(None, true, || Some(100)), Some(100));
+        assert_eq!(pick_width(Some("wide"), true, || Some(100)), Some(100));
+        assert_eq!(pick_width(Some("0"), true, || None), Some(80));
+        assert_eq!(pick_width(None, true, || None), Some(80));
+    }
+
+    #[test]
+    fn columns_should_apply_without_a_terminal() {
+        assert_eq!(pick_width(Some("20"), false, || Some(100)), Some(20));
+        assert_eq!(pick_width(Some("wide"), false, || Some(100)), None);
+        assert_eq!(pick_width(None, false, || Some(100)), None);
+    }
+
+    #[test]
+    fn grid_without_width_prints_one_per_line() {
+        let cells = [("a", 1), ("b ", 2)];
+
+        assert_eq!(grid(&cells, None, None, 1, 0), vec!["a", "b "]);
+    }
+
+    #[test]
+    fn grid_fills_columns_first() {
+        let cells = [("a", 1), ("b", 1), ("c", 1), ("d", 1), ("e", 1)];
+
+        assert_eq!(grid(&cells, Some(6), None, 1, 0), vec!["a c e", "b d"]);
+    }
+
+    #[test]
+    fn grid_columns_should_each_take_their_own_width() {
+        // Columns as wide as the widest name would only fit one of these in
+        // 16, but sized one by one three fit, as ls would lay them out
+        let cells = [
+            ("a", 1),
+            ("b", 1),
+            ("long_name", 9),
+            ("c", 1),
+            ("d", 1),
+            ("e", 1),
+        ];
+
+        let lines = grid(&cells, Some(16), None, 1, 0);
+        assert_eq!(lines, vec!["a long_name d", "b c         e"]);
+        // One column narrower and the name no longer fits beside the others
+        assert_eq!(grid(&cells, Some(11), None, 1, 0).len(), 3);
+    }
+
+    #[test]
+    fn forced_columns_should_ignore_the_width() {
+        let cells = [("a", 1), ("b", 1), ("c", 1), ("d", 1), ("e", 1)];
+
+        assert_eq!(
+            grid(&cells, Some(80), Some(2), 1, 0),
+            vec!["a d", "b e", "c"]
+        );
+        // Lines may overflow, but names are never split
+        assert_eq!(grid(&cells, Some(2), Some(4), 1, 0), vec!["a c d e", "b"]);
+        assert_eq!(grid(&cells, None, Some(9), 1, 0), vec!["a b c d e"]);
+    }
+
+    #[test]
+    fn forced_columns_should_all_be_used() {
+        let cells = [("a", 1), ("b", 1), ("c", 1), ("d", 1), ("e", 1), ("f", 1)];
+
+        // Rows of two would fill only three of the four asked for
+        assert_eq!(grid(&cells, None, Some(4), 1, 0), vec!["a c e f", "b d"]);
+    }
+
+    #[test]
+    fn grid_should_pad_only_before_another_cell() {
+        // A trailing space is part of a name, not padding to trim
+        let cells = [("a", 1), ("b ", 2), ("c", 1), ("d ", 2)];
+
+        assert_eq!(grid(&cells, Some(80), None, 1, 0), vec!["a b  c d "]);
+        assert_eq!(
+            grid(&cells, Some(80), Some(2), 2, 3),
+            vec!["a    c", "b    d "]
+        );
+    }
+
+    #[test]
+    fn truncate_should_keep_whole_characters() {
+        assert_eq!(truncate("short", 5), "short");
+        assert_eq!(truncate("longer", 5), "long…");
+        // Each of these takes two columns, so only one fits before the "…"
+        assert_eq!(truncate("日本語", 4), "日…");
+        assert_eq!(truncate("éèê", 2), "é…");
+        assert_eq!(truncate("name", 0), "");
+    }
+
+    #[test]
+    fn truncate_bytes_should_cut_between_characters() {
+        assert_eq!(truncate_bytes("short", 5), "short");
+        assert_eq!(truncate_bytes("longer", 5), "lo…");
+        // "é" takes two bytes, so there is only room for one before the "…"
+        assert_eq!(truncate_bytes("éèê", 5), "é…");
+        assert_eq!(truncate_bytes("name", 2), "");
+    }
+
+    #[test]
+    fn width_counts_terminal_columns() {
+        assert_eq!(width("abc"), 3);
+        assert_eq!(width("é"), 1);
+        assert_eq!(width("日本"), 4);
+    }
+
+    #[test]
+    fn fill_wraps_between_cells() {
+        let cells = [("alpha", 5), ("beta", 4), ("gamma", 5), ("delta", 5)];
+
+        assert_eq!(fill(&cells, 14, ","), vec!["alpha, beta,", "gamma, delta"]);
+        assert_eq!(fill(&cells, 16, ""), vec!["alpha beta gamma", "delta"]);
+    }
+
+    #[test]
+    fn fill_keeps_cells_wider_than_the_line_whole() {
+        let cells = [("a", 1), ("much_too_long", 13), ("b", 1)];
+
+        assert_eq!(fill(&cells, 5, ""), vec!["a", "much_too_long", "b"]);
+    }
+
+    #[test]
+    fn fill_should_keep_trailing_spaces() {
+        let cells = [("a ", 2), ("b", 1), ("c ", 2)];
+
+        assert_eq!(fill(&cells, 80, ","), vec!["a , b, c "]);
+    }
+
+    #[test]
+    fn grid_narrow_width_falls_back_to_one_column() {
+        let cells = [("long_name", 9), ("other", 5)];
+
+        assert_eq!(
+            grid(&cells, Some(4), None, 1, 0),
+            vec!["long_name", "other"]
+        );
+    }
+}
@@ -0,0 +1,415 @@
+use super::{
+    git,
+    options::Options,
+    owner::Owners,
+    paths::{Path, Paths},
+    record, vfs,
+};
+use glob::Pattern;
+use std::{
+    fmt,
+    fs::{self, DirEntry, ReadDir},
+    io::{self, Write},
+    panic, thread,
+};
+
+/// An entry that was found in a directory but could not be statted.
+#[derive(Debug)]
+pub struct EntryError {
+    pub name: String,
+    pub error: io::Error,
+}
+
+impl fmt::Display for EntryError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "cannot access '{}': {}",
+            self.name,
+            describe(&self.error)
+        )
+    }
+}
+
+/// An error the way `ls` words it, e.g. `Permission denied`, without the
+/// ` (os error 13)` Rust adds to system errors.
+pub fn describe(error: &io::Error) -> String {
+    let text = error.to_string();
+    match error.raw_os_error() {
+        Some(code) => text
+            .strip_suffix(&format!(" (os error {code})"))
+            .unwrap_or(&text)
+            .to_string(),
+        None => text,
+    }
+}
+
+/// Patterns from `dir/.lsrignore`, one glob per line. Blank lines and lines
+/// starting with `#` are skipped, and so are patterns that don't parse, since
+/// a typo in the file shouldn't stop the listing.
+fn ignore_file(dir: &std::path::Path) -> Vec<Pattern> {
+    let Ok(contents) = fs::read_to_string(dir.join(".lsrignore")) else {
+        return Vec::new();
+    };
+    contents
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .filter_map(|line| Pattern::new(line).ok())
+        .collect()
+}
+
+/// Fewest entries worth splitting between threads; below this, starting
+/// them costs more than the stats they would share.
+const PARALLEL_MIN_ENTRIES: usize = 512;
+
+/// Reads every entry of `dir`. Failing to open the directory is an error,
+/// while entries that can't be statted are collected in `Paths::errors` and
+/// listed with placeholders, so the rest of the listing still goes through.
+/// With `strict_utf8`, names that aren't valid UTF-8 are reported the same
+/// way and left out, instead of being shown with replacement characters.
+/// With `since_revision`, not being able to ask git what changed is an error
+/// too. Entries of a virtual filesystem like `/proc` get no size, as their
+/// lengths are made up.
+///
+/// Big directories are statted on up to `Options::threads` threads. Each
+/// takes a contiguous run of entries and the runs are put back in order, so
+/// the result is the same as reading them one by one.
+pub fn list_directory(dir: &std::path::Path, options: &Options) -> io::Result<Paths> {
+    let mut paths = for_directory(dir, options)?;
+    let mut entries = Vec::new();
+    for content in fs::read_dir(dir)? {
+        match content {
+            Ok(entry) => entries.push(entry),
+            Err(error) => paths.errors.push(EntryError {
+                name: dir.to_string_lossy().into_owned(),
+                error,
+            }),
+        }
+    }
+    let threads = if options.needs_metadata() && entries.len() >= PARALLEL_MIN_ENTRIES {
+        options.threads.clamp(1, entries.len())
+    } else {
+        1
+    };
+    let read = if threads == 1 {
+        read_entries(entries, options)
+    } else {
+        let batch = entries.len().div_ceil(threads);
+        let mut batches = Vec::new();
+        while !entries.is_empty() {
+            let rest = entries.split_off(batch.min(entries.len()));
+            batches.push(std::mem::replace(&mut entries, rest));
+        }
+        thread::scope(|scope| {
+            let handles: Vec<_> = batches
+                .into_iter()
+                .map(|batch| scope.spawn(move || read_entries(batch, options)))
+                .collect();
+            handles
+                .into_iter()
+                .flat_map(|handle| {
+                    handle
+                        .join()
+                        .unwrap_or_else(|panic| panic::resume_unwind(panic))
+                })
+                .collect()
+        })
+    };
+    for (path, error) in read {
+        paths.paths.extend(path);
+        paths.errors.extend(error);
+    }
+    Ok(paths)
+}
+
+/// An empty listing of `dir` with the options its entries are filtered by:
+/// the ones given, its `.lsrignore` and what it says about the directory.
+fn for_directory(dir: &std::path::Path, options: &Options) -> io::Result<Paths> {
+    let mut paths = Paths::with_options(options.clone());
+    paths.options.ignore.extend(ignore_file(dir));
+    paths.options.virtual_fs = vfs::is_virtual(dir);
+    if let Some(reference) = &options.since_revision {
+        paths.options.changed = Some(git::changed_since(dir, reference)?);
+    }
+    Ok(paths)
+}
+
+/// Turns entries into paths, in order.
+fn read_entries(
+    entries: Vec<DirEntry>,
+    options: &Options,
+) -> Vec<(Option<Path>, Option<EntryError>)> {
+    let mut owners = Owners::default();
+    entries
+        .into_iter()
+        .map(|entry| read_entry(entry, options, &mut owners))
+        .collect()
+}
+
+/// An entry gives a path, an error, or a placeholder path along with the
+/// error that kept it from being read.
+fn read_entry(
+    entry: DirEntry,
+    options: &Options,
+    owners: &mut Owners,
+) -> (Option<Path>, Option<EntryError>) {
+    let name = entry.file_name().to_string_lossy().into_owned();
+    if options.strict_utf8 && entry.file_name().to_str().is_none() {
+        let error = io::Error::new(io::ErrorKind::InvalidData, "invalid UTF-8 in file name");
+        return (None, Some(EntryError { name, error }));
+    }
+    let file_type = entry.file_type().ok();
+    let full_path = entry.path();
+    if file_type.is_some() && options.names_only {
+        return (Some(Path::from_file_type_alone(full_path, file_type)), None);
+    }
+    if file_type.is_some() && !options.needs_metadata() {
+        return (Some(Path::from_file_type(full_path, file_type)), None);
+    }
+    match Path::with_owners(entry, owners) {
+        Ok(path) => (Some(path), None),
+        Err(error) => (
+            Some(Path::unreadable(full_path, file_type)),
+            Some(EntryError { name, error }),
+        ),
+    }
+}
+
+/// The entries of a directory as `--jsonl` lines, each made as soon as the
+/// entry is read instead of after the whole directory, so they come in the
+/// order the directory gives them rather than sorted. Entries the listing
+/// would hide are skipped. Like `read_entry`, each step gives a line, an
+/// error, or the line of a placeholder along with its error.
+pub struct JsonLines {
+    dir: String,
+    entries: ReadDir,
+    filter: Paths,
+    owners: Owners,
+}
+
+impl Iterator for JsonLines {
+    type Item = (Option<String>, Option<EntryError>);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let entry = match self.entries.next()? {
+                Ok(entry) => entry,
+                Err(error) => {
+                    let name = self.dir.clone();
+                    return Some((None, Some(EntryError { name, error })));
+                }
+            };
+            let (path, error) = read_entry(entry, &self.filter.options, &mut self.owners);
+            let path = path.filter(|path| self.filter.shows(path));
+            if path.is_some() || error.is_some() {
+                let line = path.map(|path| record::to_json_line(&path.record()));
+                return Some((line, error));
+            }
+        }
+    }
+}
+
+/// Starts streaming `dir` as JSON lines. Like `list_directory`, failing to
+/// open it is an error.
+pub fn json_lines(dir: &std::path::Path, options: &Options) -> io::Result<JsonLines> {
+    let filter = for_directory(dir, options)?;
+    Ok(JsonLines {
+        dir: dir.to_string_lossy().into_owned(),
+        entries: fs::read_dir(dir)?,
+        filter,
+        owners: Owners::default(),
+    })
+}
+
+/// Writes the listing to `out` exactly as the `lsr` binary prints it.
+pub fn render(paths: Paths, out: &mut impl Write) -> io::Result<()> {
+    paths.write_to(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::path::{
+        layout::plain,
+        options::{Indicators, SortKey},
+    };
+
+    #[test]
+    fn list_directory_should_find_entries() {
+        let paths = list_directory(std::path::Path::new("src"), &Options::default()).unwrap();
+
+        let mut out = Vec::new();
+        render(paths, &mut out).unwrap();
+
+        let out = String::from_utf8(out).unwrap();
+        assert!(out.lines().any(|line| line.contains("main.rs")));
+    }
+
+    #[test]
+    fn describe_should_drop_the_os_error_code() {
+        let denied = io::Error::from_raw_os_error(libc::EACCES);
+
+        assert_eq!(describe(&denied), "Permission denied");
+        assert_eq!(describe(&io::Error::other("custom")), "custom");
+    }
+
+    #[test]
+    fn lsrignore_should_add_to_the_ignore_patterns() {
+        let dir = std::env::temp_dir().join("lsr_lsrignore");
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join(".lsrignore"), "# build output\n*.o\n\n").unwrap();
+        for name in ["main.c", "main.o", "notes.tmp"] {
+            fs::write(dir.join(name), "").unwrap();
+        }
+
+        let options = Options {
+            ignore: vec![Pattern::new("*.tmp").unwrap()],
+            ..Default::default()
+        };
+        let paths = list_directory(&dir, &options).unwrap();
+        fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(plain(&paths.render()), "main.c\n");
+    }
+
+    #[test]
+    fn name_only_listing_should_not_stat() {
+        let paths = list_directory(std::path::Path::new("src"), &Options::default()).unwrap();
+        let records: Vec<_> = paths.paths.iter().map(|path| path.record()).collect();
+
+        assert!(records.iter().all(|record| record.size.is_none()));
+        assert!(records.iter().any(|record| record.kind == "directory"));
+    }
+
+    #[test]
+    fn names_only_should_not_stat_even_to_sort() {
+        use colored::Colorize;
+
+        let dir = std::env::temp_dir().join("lsr_names_only");
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(dir.join("sub")).unwrap();
+        fs::write(dir.join("file"), "data").unwrap();
+        #[cfg(unix)]
+        std::os::unix::fs::symlink("missing", dir.join("link")).unwrap();
+        let options = Options {
+            names_only: true,
+            sort: SortKey::Size,
+            indicators: Indicators::Classify,
+            ..Default::default()
+        };
+        let paths = list_directory(&dir, &options).unwrap();
+        fs::remove_dir_all(&dir).unwrap();
+        let records: Vec<_> = paths.paths.iter().map(|path| path.record()).collect();
+
+        assert!(records.iter().all(|record| record.size.is_none()));
+        assert!(records.iter().all(|record| record.modified.is_none()));
+        assert!(records.iter().any(|record| record.kind == "directory"));
+        // Not even a link is read to see where it points, so nothing tells
+        // that this one is broken
+        colored::control::set_override(true);
+        assert!(!paths.render().contains(&"link".red().to_string()));
+    }
+
+    #[test]
+    fn long_listing_should_stat() {
+        let options = Options {
+            long: true,
+            ..Default::default()
+        };
+        let paths = list_directory(std::path::Path::new("src"), &options).unwrap();
+
+        assert!(paths.paths.iter().all(|path| path.record().size.is_some()));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn strict_utf8_should_report_and_skip_invalid_names() {
+        use std::{ffi::OsStr, os::unix::ffi::OsStrExt};
+
+        let dir = std::env::temp_dir().join("lsr_strict_utf8");
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join(OsStr::from_bytes(b"bad\xff")), "").unwrap();
+        fs::write(dir.join("good"), "").unwrap();
+        let strict = Options {
+            strict_utf8: true,
+            ..Default::default()
+        };
+
+        let lossy = list_directory(&dir, &Options::default()).unwrap();
+        let paths = list_directory(&dir, &strict).unwrap();
+        fs::remove_dir_all(&dir).unwrap();
+
+        let names = |paths: &Paths| -> Vec<String> {
+            let mut names: Vec<_> = paths.paths.iter().map(|path| path.record().name).collect();
+            names.sort();
+            names
+        };
+        assert_eq!(names(&lossy), ["bad\u{fffd}", "good"]);
+        assert_eq!(names(&paths), ["good"]);
+        assert_eq!(
+            paths.errors[0].to_string(),
+            "cannot access 'bad\u{fffd}': invalid UTF-8 in file name"
+        );
+    }
+
+    #[test]
+    fn threads_should_not_change_the_listing() {
+        let dir = std::env::temp_dir().join("lsr_threads");
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        for i in 0..2 * PARALLEL_MIN_ENTRIES {
+            fs::write(dir.join(format!("file{i}")), "x".repeat(i % 7)).unwrap();
+        }
+        let options = |threads| Options {
+            long: true,
+            threads,
+            ..Default::default()
+        };
+
+        let records =
+            |paths: Paths| -> Vec<_> { paths.paths.iter().map(|path| path.record()).collect() };
+        let serial = records(list_directory(&dir, &options(1)).unwrap());
+        let parallel = records(list_directory(&dir, &options(8)).unwrap());
+        fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(serial.len(), 2 * PARALLEL_MIN_ENTRIES);
+        assert_eq!(serial, parallel);
+    }
+
+    #[test]
+    fn json_lines_should_give_what_the_listing_shows() {
+        let dir = std::env::temp_dir().join("lsr_json_lines");
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(dir.join("sub")).unwrap();
+        fs::write(dir.join("file"), "12345").unwrap();
+        fs::write(dir.join(".hidden"), "").unwrap();
+        let options = Options {
+            output: crate::path::options::Output::JsonLines,
+            ..Default::default()
+        };
+
+        let mut lines: Vec<_> = json_lines(&dir, &options)
+            .unwrap()
+            .map(|(line, error)| {
+                assert!(error.is_none());
+                line.unwrap()
+            })
+            .collect();
+        fs::remove_dir_all(&dir).unwrap();
+
+        lines.sort();
+        assert_eq!(lines.len(), 2);
+        assert!(lines[0].starts_with(r#"{"name":"file","type":"file","size":5,"modified":""#));
+        assert!(lines[1].starts_with(r#"{"name":"sub","type":"directory","size":"#));
+    }
+
+    #[test]
+    fn list_directory_should_fail_on_missing_dir() {
+        let result = list_directory(std::path::Path::new("does-not-exist"), &Options::default());
+
+        assert!(result.is_err());
+    }
+}
@@ -0,0 +1,133 @@
+use colored::{Color, ColoredString, Colorize};
+
+/// What BSD and macOS `ls` use when `LSCOLORS` is unset.
+const DEFAULT: &str = "exfxcxdxbxegedabagacad";
+
+/// The kinds of entry `LSCOLORS` gives a color, in the order of its pairs.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Slot {
+    Directory,
+    Symlink,
+    Socket,
+    Pipe,
+    Executable,
+    BlockDevice,
+    CharDevice,
+    Setuid,
+    Setgid,
+    /// A directory others can write to, with the sticky bit.
+    SharedSticky,
+    /// A directory others can write to, without it.
+    Shared,
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+struct Style {
+    foreground: Option<Color>,
+    bold: bool,
+    background: Option<Color>,
+}
+
+/// The BSD `LSCOLORS` scheme: a foreground and a background letter for each
+/// `Slot`, `a` to `h` for black, red, green, brown, blue, magenta, cyan and
+/// light grey, in capitals for bold, and `x` for the terminal's own color.
+/// A shorter value leaves the slots after it at the BSD default.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LsColors {
+    styles: [Style; 11],
+}
+
+impl LsColors {
+    /// The scheme from `$LSCOLORS`, unless it is unset or not a valid one,
+    /// in which case the usual colors stay.
+    pub fn from_env() -> Option<Self> {
+        std::env::var("LSCOLORS")
+            .ok()
+            .and_then(|value| LsColors::parse(&value))
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        let letters: Vec<char> = value.chars().collect();
+        let defaults: Vec<char> = DEFAULT.chars().collect();
+        if letters.len() > defaults.len() {
+            return None;
+        }
+        let mut styles = [Style::default(); 11];
+        for (i, style) in styles.iter_mut().enumerate() {
+            let letter = |at: usize| letters.get(at).copied().unwrap_or(defaults[at]);
+            let (foreground, bold) = color(letter(2 * i))?;
+            let (background, _) = color(letter(2 * i + 1))?;
+            *style = Style {
+                foreground,
+                bold,
+                background,
+            };
+        }
+        Some(LsColors { styles })
+    }
+
+    pub fn paint(&self, text: &str, slot: Slot) -> ColoredString {
+        let style = self.styles[slot as usize];
+        let mut painted = text.normal();
+        if let Some(foreground) = style.foreground {
+            painted = painted.color(foreground);
+        }
+        if style.bold {
+            painted = painted.bold();
+        }
+        if let Some(background) = style.background {
+            painted = painted.on_color(background);
+        }
+        painted
+    }
+}
+
+/// The color a letter stands for and whether it is a bold one, or `None`
+/// for a letter that isn't part of the scheme.
+fn color(letter: char) -> Option<(Option<Color>, bool)> {
+    let colors = [
+        Color::Black,
+        Color::Red,
+        Color::Green,
+        Color::Yellow,
+        Color::Blue,
+        Color::Magenta,
+        Color::Cyan,
+        Color::White,
+    ];
+    match letter {
+        'x' | 'X' => Some((None, false)),
+        'a'..='h' => Some((Some(colors[letter as usize - 'a' as usize]), false)),
+        'A'..='H' => Some((Some(colors[letter as usize - 'A' as usize]), true)),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn pairs_should_give_foreground_and_background() {
+        let colors = LsColors::parse("Gxfxcxdxbxegedabagacad").unwrap();
+
+        assert_eq!(colors.paint("d", Slot::Directory), "d".cyan().bold());
+        assert_eq!(colors.paint("l", Slot::Symlink), "l".magenta());
+        assert_eq!(colors.paint("b", Slot::BlockDevice), "b".blue().on_cyan());
+    }
+
+    #[test]
+    fn short_values_should_keep_the_default_for_the_rest() {
+        let short = LsColors::parse("Ex").unwrap();
+        let default = LsColors::parse(DEFAULT).unwrap();
+
+        assert_eq!(short.paint("d", Slot::Directory), "d".blue().bold());
+        assert_eq!(short.paint("p", Slot::Pipe), default.paint("p", Slot::Pipe));
+    }
+
+    #[test]
+    fn invalid_values_should_be_ignored() {
+        assert_eq!(LsColors::parse("zx"), None);
+        assert_eq!(LsColors::parse(&"x".repeat(23)), None);
+    }
+}
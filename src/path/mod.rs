@@ -1 +1,21 @@
+pub mod archive;
+pub mod dedup;
+pub mod diff;
+pub mod git;
+pub mod home;
+pub mod layout;
+pub mod listing;
+pub mod lscolors;
+pub mod natural;
+pub mod options;
+pub mod owner;
 pub mod paths;
+pub mod record;
+pub mod shuffle;
+pub mod size;
+pub mod theme;
+pub mod time;
+pub mod tree;
+pub mod usage;
+pub mod vfs;
+pub mod walk;
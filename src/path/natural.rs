@@ -0,0 +1,102 @@
+use std::cmp::Ordering;
+
+/// Compares names the way people count, for `--sort=version`: runs of
+/// digits go by their value, so `chunk_9` comes before `chunk_10`, and
+/// everything else character by character. Leading zeros don't change a
+/// value; between names that only differ in them, the first run with more
+/// zeros goes first, so `x01` comes before `x1`. Runs can be any length, as
+/// they are compared as text rather than parsed.
+pub fn compare(a: &str, b: &str) -> Ordering {
+    let (mut a_rest, mut b_rest) = (a, b);
+    // Decided by the first run that only differs by its zeros, but only
+    // once everything else turns out equal
+    let mut padding = Ordering::Equal;
+    loop {
+        match (a_rest.chars().next(), b_rest.chars().next()) {
+            (None, None) => return padding,
+            (None, Some(_)) => return Ordering::Less,
+            (Some(_), None) => return Ordering::Greater,
+            (Some(x), Some(y)) if x.is_ascii_digit() && y.is_ascii_digit() => {
+                let (a_run, a_after) = split_digits(a_rest);
+                let (b_run, b_after) = split_digits(b_rest);
+                let (a_value, b_value) =
+                    (a_run.trim_start_matches('0'), b_run.trim_start_matches('0'));
+                let value = a_value
+                    .len()
+                    .cmp(&b_value.len())
+                    .then_with(|| a_value.cmp(b_value));
+                if value != Ordering::Equal {
+                    return value;
+                }
+                if padding == Ordering::Equal {
+                    padding = b_run.len().cmp(&a_run.len());
+                }
+                (a_rest, b_rest) = (a_after, b_after);
+            }
+            (Some(x), Some(y)) if x != y => return x.cmp(&y),
+            (Some(x), Some(_)) => {
+                (a_rest, b_rest) = (&a_rest[x.len_utf8()..], &b_rest[x.len_utf8()..]);
+            }
+        }
+    }
+}
+
+/// The leading run of ASCII digits and what follows it.
+fn split_digits(text: &str) -> (&str, &str) {
+    let end = text
+        .find(|c: char| !c.is_ascii_digit())
+        .unwrap_or(text.len());
+    text.split_at(end)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sorted(names: &[&str]) -> Vec<String> {
+        let mut names: Vec<String> = names.iter().map(|name| name.to_string()).collect();
+        names.sort_by(|a, b| compare(a, b));
+        names
+    }
+
+    #[test]
+    fn numbers_should_go_by_value() {
+        assert_eq!(
+            sorted(&["chunk_100", "chunk_9", "chunk_10"]),
+            ["chunk_9", "chunk_10", "chunk_100"]
+        );
+        assert_eq!(
+            sorted(&["chunk_000100", "chunk_9", "chunk_0010"]),
+            ["chunk_9", "chunk_0010", "chunk_000100"]
+        );
+    }
+
+    #[test]
+    fn leading_zeros_should_only_break_ties() {
+        assert_eq!(compare("x01", "x1"), Ordering::Less);
+        assert_eq!(compare("x1", "x01"), Ordering::Greater);
+        assert_eq!(compare("x001", "x01"), Ordering::Less);
+        assert_eq!(compare("x01", "x01"), Ordering::Equal);
+        // Anything after the numbers matters more than their zeros
+        assert_eq!(compare("x01b", "x1a"), Ordering::Greater);
+        // The first run with different zeros decides
+        assert_eq!(compare("1.01", "01.1"), Ordering::Greater);
+        assert_eq!(compare("0", "00"), Ordering::Greater);
+    }
+
+    #[test]
+    fn text_should_compare_by_character() {
+        assert_eq!(sorted(&["b", "a2", "a10", "a"]), ["a", "a2", "a10", "b"]);
+        assert_eq!(compare("v1.2", "v1.10"), Ordering::Less);
+        assert_eq!(compare("a1", "a_1"), Ordering::Less);
+        assert_eq!(compare("é2", "é10"), Ordering::Less);
+    }
+
+    #[test]
+    fn long_runs_should_not_overflow() {
+        let big = format!("file{}", "9".repeat(40));
+        let bigger = format!("file1{}", "0".repeat(40));
+
+        assert_eq!(compare(&big, &bigger), Ordering::Less);
+    }
+}
@@ -0,0 +1,657 @@
+use super::{
+    home,
+    lscolors::LsColors,
+    size::{BarScale, BlockSize, HumanSize, SizeStyle},
+    theme::Theme,
+    time::{Precision, TimeKind, TimeStyle},
+};
+use clap::ValueEnum;
+use glob::Pattern;
+use std::{collections::HashSet, path::PathBuf, time::SystemTime};
+
+/// When to emit color escapes.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum ColorMode {
+    /// Color only when stdout is a terminal, unless `NO_COLOR` or
+    /// `CLICOLOR=0` turn it off or `CLICOLOR_FORCE` turns it on anyway, as
+    /// with BSD `ls`.
+    #[default]
+    Auto,
+    Always,
+    Never,
+}
+
+impl ColorMode {
+    /// Colors are drawn by `colored`, whose switch is process-wide.
+    pub fn apply(self) {
+        match self {
+            ColorMode::Auto => colored::control::unset_override(),
+            ColorMode::Always => colored::control::set_override(true),
+            ColorMode::Never => colored::control::set_override(false),
+        }
+    }
+}
+
+/// Which side of a column the text sticks to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum Align {
+    Left,
+    Right,
+}
+
+/// Order in which entries are listed.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum SortKey {
+    /// Alphabetically by name.
+    #[default]
+    Name,
+    /// Largest first.
+    Size,
+    /// Most recently modified first.
+    Time,
+    /// Shortest name first, counted in characters.
+    Length,
+    /// By name, with numbers in names going by their value.
+    #[value(alias = "natural")]
+    Version,
+    /// Shuffled, in an order `seed` picks.
+    Random,
+    /// In the order the directory yields them.
+    None,
+}
+
+/// Order in which a recursive listing visits directories.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum Traversal {
+    /// Each subdirectory is finished before its next sibling, like `ls -R`.
+    #[default]
+    Dfs,
+    /// All directories of one level before any of the next.
+    Bfs,
+}
+
+/// A column of the long format.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum Column {
+    Perms,
+    Links,
+    Owner,
+    Size,
+    Time,
+    Name,
+    /// The part of a file's name after its last dot.
+    Ext,
+    /// Space allocated on disk over the length, flagging sparse files and
+    /// ones with slack.
+    Frag,
+    /// Where a symlink points, or `-` for anything else. With this column
+    /// the name is printed without ` -> target`.
+    Target,
+}
+
+impl Column {
+    /// The columns `ls -l` prints, in its order.
+    pub const DEFAULT: [Column; 6] = [
+        Column::Perms,
+        Column::Links,
+        Column::Owner,
+        Column::Size,
+        Column::Time,
+        Column::Name,
+    ];
+}
+
+/// Which entries get a type marker after their name. The markers are plain
+/// text, so they show with or without colors.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum Indicators {
+    #[default]
+    None,
+    /// `*` after executable files only.
+    Executables,
+    /// `/` after directories, `*` after executables and `@` after symlinks.
+    Classify,
+}
+
+/// How names are laid out outside the long format.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum Arrange {
+    /// Aligned columns, filled top to bottom.
+    #[default]
+    Grid,
+    /// Lines filled like a paragraph.
+    Fill,
+    /// Like `Fill`, with a comma after each name.
+    Commas,
+}
+
+/// Which files `--dedup` and `--show-dups-only` keep, going by content.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Dedup {
+    /// Leave out files that copy one listed before them.
+    HideCopies,
+    /// Show only those copies.
+    CopiesOnly,
+}
+
+/// What is shown as an entry's name.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum NameStyle {
+    /// The name in its directory.
+    #[default]
+    Base,
+    /// The full path from the root, with symlinks along the way kept.
+    Absolute,
+    /// The full path with every symlink resolved, falling back to the
+    /// absolute one when that fails, e.g. for a dangling link.
+    Canonical,
+}
+
+/// How the listing is written out.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum Output {
+    /// Aligned, colored text.
+    #[default]
+    Text,
+    Yaml,
+    Toml,
+    /// One JSON object per entry and line.
+    JsonLines,
+    /// The names, shell-quoted, on one line.
+    Shell,
+    /// A tree as nested JSON objects; anything else as JSON lines.
+    Json,
+    /// Only how many entries would be shown.
+    Count,
+}
+
+/// What picks the color of each name. Only one strategy applies, so they
+/// can't disagree; a new one is a variant here and a function in `Path`.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum ColorBy {
+    /// Directories, files and broken links each in their own color.
+    #[default]
+    Type,
+    /// Files by their extension, directories as by type.
+    Extension,
+    /// By how long ago the entry was modified, like `--heatmap` does times.
+    Age,
+    /// Names stay uncolored.
+    None,
+}
+
+/// What kind of file an entry is, by the letter `ls -l` and `find -type`
+/// give it.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum FileKind {
+    /// A regular file.
+    #[default]
+    #[value(name = "f")]
+    File,
+    #[value(name = "d")]
+    Dir,
+    #[value(name = "l")]
+    Symlink,
+    /// A named pipe.
+    #[value(name = "p")]
+    Fifo,
+    #[value(name = "s")]
+    Socket,
+    /// A block device.
+    #[value(name = "b")]
+    Block,
+    /// A character device.
+    #[value(name = "c")]
+    Char,
+}
+
+impl FileKind {
+    /// Everything that is none of the special kinds counts as a file.
+    pub fn of(file_type: std::fs::FileType) -> Self {
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::FileTypeExt;
+
+            if file_type.is_fifo() {
+                return FileKind::Fifo;
+            } else if file_type.is_socket() {
+                return FileKind::Socket;
+            } else if file_type.is_block_device() {
+                return FileKind::Block;
+            } else if file_type.is_char_device() {
+                return FileKind::Char;
+            }
+        }
+        if file_type.is_dir() {
+            FileKind::Dir
+        } else if file_type.is_symlink() {
+            FileKind::Symlink
+        } else {
+            FileKind::File
+        }
+    }
+
+    /// The first character of the permissions in the long format, `-` for
+    /// a regular file.
+    pub fn letter(self) -> char {
+        match self {
+            FileKind::File => '-',
+            FileKind::Dir => 'd',
+            FileKind::Symlink => 'l',
+            FileKind::Fifo => 'p',
+            FileKind::Socket => 's',
+            FileKind::Block => 'b',
+            FileKind::Char => 'c',
+        }
+    }
+}
+
+/// Everything that controls how a listing is gathered and printed.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Options {
+    /// Show entries whose name starts with a dot.
+    pub all: bool,
+    /// Show only hidden entries, the opposite of the default filter.
+    pub dotfiles_only: bool,
+    /// Names hidden like dotfiles, so `all` shows them again.
+    pub ignore: Vec<Pattern>,
+    /// Show only entries of these kinds, or of any when empty.
+    pub kinds: Vec<FileKind>,
+    /// Show only entries modified at or after this time.
+    pub modified_since: Option<SystemTime>,
+    /// Show only entries changed since this git revision.
+    pub since_revision: Option<String>,
+    /// Names in the directory being listed that changed since
+    /// `since_revision`, filled in as each directory is read.
+    pub changed: Option<HashSet<String>>,
+    /// Whether the directory being listed is on a virtual filesystem like
+    /// `/proc`, where a length of 0 says nothing. Filled in as it is read.
+    pub virtual_fs: bool,
+    /// Filter files by whether their content repeats an earlier entry's.
+    pub dedup: Option<Dedup>,
+    /// Leave out and report names that aren't valid UTF-8.
+    pub strict_utf8: bool,
+    /// Most threads to stat a big directory's entries on.
+    pub threads: usize,
+    /// Never stat entries, showing only what the directory itself tells:
+    /// names and types.
+    pub names_only: bool,
+    /// List each entry's extended attributes under it in the long format.
+    pub xattrs: bool,
+    /// Cut names longer than this many bytes short, so one absurd name
+    /// can't stretch the columns of every other line.
+    pub max_name_bytes: Option<usize>,
+    /// Make the name column exactly this wide, cutting longer names short,
+    /// instead of as wide as the longest name.
+    pub name_width: Option<usize>,
+    /// Show names as they are, or as absolute paths.
+    pub name_style: NameStyle,
+    /// Write full paths relative to this canonical directory, when they are
+    /// inside it.
+    pub relative_to: Option<PathBuf>,
+    /// Write paths under the home directory starting with `~`.
+    pub collapse_home: bool,
+    /// End each line with a NUL byte instead of a newline.
+    pub zero: bool,
+    /// One entry per line with permissions, links, owner, size and time.
+    pub long: bool,
+    /// Which columns the long format shows, in order.
+    pub columns: Vec<Column>,
+    /// Pad the long format's columns so they line up.
+    pub aligned: bool,
+    /// Put between the long format's columns.
+    pub separator: String,
+    /// Display the entries as a tree rooted at the given directory.
+    pub tree: Option<String>,
+    /// Leave everything but directories out of the tree.
+    pub dirs_only: bool,
+    /// Name each entry of the tree by its path from the root.
+    pub tree_relative: bool,
+    /// List subdirectories too, each under its own header.
+    pub recursive: bool,
+    /// Order of the directories in a recursive listing.
+    pub traversal: Traversal,
+    /// Let a recursive listing descend into symlinks to directories.
+    pub follow_symlinks: bool,
+    /// How deep a recursive listing or tree goes, 1 being the root's own
+    /// entries. `None` has no limit.
+    pub max_depth: Option<usize>,
+    /// Spaces before every line of the listing, for nesting it under others.
+    pub margin: usize,
+    /// Print "(empty)" when nothing would be shown.
+    pub empty_message: bool,
+    /// Show how many entries each listed directory holds.
+    pub child_count: bool,
+    /// Gather what `--watch --diff` compares refreshes by, the size and
+    /// time of every entry, even when nothing shows them.
+    pub watch_diff: bool,
+    /// Note which listed directories have nothing in them to show.
+    pub mark_empty: bool,
+    /// Type markers appended to names.
+    pub indicators: Indicators,
+    /// Mark symlinks to directories and executables like their targets
+    /// instead of with `@`.
+    pub classify_targets: bool,
+    /// Print how many directories, files and symlinks were listed.
+    pub count: bool,
+    /// Print the combined size of the listed entries.
+    pub total: bool,
+    /// Name the largest file and the oldest entry below the listing.
+    pub highlights: bool,
+    /// Let `count` and `total` include hidden files even without `all`.
+    pub count_all: bool,
+    /// Layout of the names when not in the long format.
+    pub arrange: Arrange,
+    /// Grid columns to lay the names out in whatever `width` says, as
+    /// opposed to the long format's `columns`.
+    pub grid_columns: Option<usize>,
+    /// Columns available to the grid and fill layouts, from the terminal or
+    /// `--output-width`. `None` prints one entry per line.
+    pub width: Option<usize>,
+    /// Show every size in this fixed unit instead of picking one per entry.
+    pub block_size: Option<BlockSize>,
+    /// Pick a 1024- or 1000-based unit per entry like `ls -h` / `ls --si`.
+    pub human: Option<HumanSize>,
+    /// Show every size as an exact byte count, whatever the other size
+    /// options say.
+    pub raw_bytes: bool,
+    /// Show the exact byte count and the human-readable size side by side.
+    pub size_both: bool,
+    /// Show the space allocated on disk instead of the apparent length.
+    pub actual_size: bool,
+    /// Give directories the size of the files below them, like `du`.
+    pub dir_sizes: bool,
+    /// Start each entry with the blocks it takes on disk, in `block_size`
+    /// units or 1024 bytes, and head the listing with their `total`.
+    pub size_blocks: bool,
+    /// Head a long listing with the `total` blocks, like `ls -l`, without
+    /// giving each entry its count.
+    pub blocks_total: bool,
+    /// Draw a bar next to each file's size, scaled to the largest one.
+    pub bars: bool,
+    /// Whether bar lengths follow the sizes or their logarithms.
+    pub bar_scale: BarScale,
+    /// Spelling of the unit suffixes.
+    pub size_style: SizeStyle,
+    /// Alignment of the name column.
+    pub name_align: Align,
+    /// Alignment of the size column.
+    pub size_align: Align,
+    /// Which timestamp the time column shows and `SortKey::Time` sorts by.
+    pub time_kind: TimeKind,
+    /// How timestamps are written.
+    pub time_style: TimeStyle,
+    /// Resolution of the time of day in the default style.
+    pub time_precision: Precision,
+    /// Write timestamps in UTC instead of the local timezone.
+    pub utc: bool,
+    /// Order of the entries.
+    pub sort: SortKey,
+    /// Picks the order of `SortKey::Random`; a listing shuffled with the same
+    /// seed comes out the same.
+    pub seed: u64,
+    /// List in the opposite order, within each directory of a tree too.
+    pub reverse: bool,
+    /// When to color the output.
+    pub color: ColorMode,
+    /// Which colors the listing is drawn in.
+    pub theme: Theme,
+    /// How names are colored.
+    pub color_by: ColorBy,
+    /// Colors for each type of entry from BSD's `LSCOLORS`, in place of the
+    /// theme's when names are colored by type.
+    pub ls_colors: Option<LsColors>,
+    /// Color sizes on a truecolor gradient relative to the largest file,
+    /// instead of by magnitude.
+    pub color_scale: bool,
+    /// Color the time column by how old each entry is.
+    pub heatmap: bool,
+    /// Set apart the times at or after this one, e.g. the last hour.
+    pub recent_since: Option<SystemTime>,
+    /// Text for people, or a structured format for other tools.
+    pub output: Output,
+}
+
+/// The name a value goes by on the command line, e.g. `name` for
+/// `SortKey::Name`.
+fn named(value: &impl ValueEnum) -> String {
+    value
+        .to_possible_value()
+        .map_or(String::new(), |value| value.get_name().to_string())
+}
+
+impl Default for Options {
+    fn default() -> Self {
+        Options {
+            all: false,
+            dotfiles_only: false,
+            ignore: Vec::new(),
+            kinds: Vec::new(),
+            modified_since: None,
+            since_revision: None,
+            changed: None,
+            virtual_fs: false,
+            dedup: None,
+            strict_utf8: false,
+            threads: 1,
+            names_only: false,
+            xattrs: false,
+            max_name_bytes: None,
+            name_width: None,
+            name_style: NameStyle::Base,
+            relative_to: None,
+            collapse_home: false,
+            zero: false,
+            long: false,
+            columns: Column::DEFAULT.to_vec(),
+            aligned: true,
+            separator: " ".to_string(),
+            tree: None,
+            dirs_only: false,
+            tree_relative: false,
+            recursive: false,
+            traversal: Traversal::default(),
+            follow_symlinks: false,
+            max_depth: None,
+            margin: 0,
+            empty_message: false,
+            child_count: false,
+            mark_empty: false,
+            watch_diff: false,
+            indicators: Indicators::None,
+            classify_targets: false,
+            count: false,
+            total: false,
+            highlights: false,
+            count_all: false,
+            arrange: Arrange::default(),
+            grid_columns: None,
+            width: None,
+            block_size: None,
+            human: None,
+            raw_bytes: false,
+            size_both: false,
+            actual_size: false,
+            dir_sizes: false,
+            size_blocks: false,
+            blocks_total: false,
+            bars: false,
+            bar_scale: BarScale::default(),
+            size_style: SizeStyle::default(),
+            name_align: Align::Left,
+            size_align: Align::Right,
+            time_kind: TimeKind::default(),
+            time_style: TimeStyle::default(),
+            time_precision: Precision::default(),
+            utc: false,
+            sort: SortKey::default(),
+            seed: 0,
+            reverse: false,
+            color: ColorMode::default(),
+            theme: Theme::default(),
+            color_by: ColorBy::default(),
+            ls_colors: None,
+            color_scale: false,
+            heatmap: false,
+            recent_since: None,
+            output: Output::default(),
+        }
+    }
+}
+
+impl Options {
+    /// `path` as listings write it, with `~` for the home directory when
+    /// `collapse_home` asks for it.
+    pub fn shown_path(&self, path: &std::path::Path) -> String {
+        if self.collapse_home {
+            home::collapse(path)
+        } else {
+            path.to_string_lossy().into_owned()
+        }
+    }
+
+    /// Whether entries need a full stat, or their name and type are enough.
+    /// Windows gets the metadata for free with the directory listing, and it
+    /// carries the hidden attribute, so it always uses it unless told not to
+    /// with `names_only`.
+    pub fn needs_metadata(&self) -> bool {
+        !self.names_only && self.wants_metadata()
+    }
+
+    fn wants_metadata(&self) -> bool {
+        cfg!(windows)
+            || self.long
+            || matches!(self.sort, SortKey::Size | SortKey::Time)
+            || !matches!(self.output, Output::Text | Output::Shell | Output::Count)
+            || self.total
+            || self.size_blocks
+            || self.blocks_total
+            || self.highlights
+            || self.color_by == ColorBy::Age
+            || self.watch_diff
+            || self.modified_since.is_some()
+            || self.indicators != Indicators::None
+    }
+
+    /// What the options amount to, one readable `setting: value` line each,
+    /// for `--explain`. Only what is set to something is mentioned among the
+    /// filters, while the layout, order, time, sizes and colors always are.
+    pub fn explain(&self) -> Vec<String> {
+        let mut lines = Vec::new();
+        let layout = if let Some(root) = &self.tree {
+            format!("tree of {root}")
+        } else if self.long {
+            let columns: Vec<_> = self.columns.iter().map(named).collect();
+            format!("long, columns {}", columns.join(","))
+        } else {
+            match self.arrange {
+                Arrange::Grid => "grid",
+                Arrange::Fill => "filled lines",
+                Arrange::Commas => "comma-separated",
+            }
+            .to_string()
+        };
+        let width = match (self.grid_columns, self.width) {
+            (Some(columns), _) => format!("{columns} columns"),
+            (None, Some(width)) => format!("{width} wide"),
+            (None, None) => "one entry per line".to_string(),
+        };
+        lines.push(format!("layout: {layout} ({width})"));
+        if self.recursive {
+            let depth = self
+                .max_depth
+                .map_or(String::new(), |depth| format!(", at most {depth} deep"));
+            lines.push(format!("recursive: {}{depth}", named(&self.traversal)));
+        }
+        let order = if self.reverse { ", reversed" } else { "" };
+        // Printed so a shuffle that turned out useful can be had again
+        let seed = if self.sort == SortKey::Random {
+            format!(", seed {}", self.seed)
+        } else {
+            String::new()
+        };
+        lines.push(format!("sort: {}{seed}{order}", named(&self.sort)));
+
+        let hidden = if self.dotfiles_only {
+            "only hidden entries"
+        } else if self.all {
+            "shown"
+        } else {
+            "hidden"
+        };
+        lines.push(format!("hidden files: {hidden}"));
+        if !self.ignore.is_empty() {
+            let patterns: Vec<_> = self.ignore.iter().map(Pattern::as_str).collect();
+            lines.push(format!("ignore: {}", patterns.join(", ")));
+        }
+        if !self.kinds.is_empty() {
+            let kinds: Vec<_> = self.kinds.iter().map(named).collect();
+            lines.push(format!("types: {}", kinds.join(", ")));
+        }
+        if let Some(since) = self.modified_since {
+            let since = chrono::DateTime::<chrono::Local>::from(since).to_rfc3339();
+            lines.push(format!("modified since: {since}"));
+        }
+        if let Some(reference) = &self.since_revision {
+            lines.push(format!("changed since: {reference}"));
+        }
+        match self.dedup {
+            Some(Dedup::HideCopies) => lines.push("duplicates: hidden".to_string()),
+            Some(Dedup::CopiesOnly) => lines.push("duplicates: only".to_string()),
+            None => {}
+        }
+
+        let zone = if self.utc { "UTC" } else { "local time" };
+        lines.push(format!(
+            "time: {}, {} style, {zone}",
+            named(&self.time_kind),
+            named(&self.time_style)
+        ));
+        let units = if self.raw_bytes {
+            "bytes".to_string()
+        } else if self.size_both {
+            "bytes and human-readable".to_string()
+        } else if let Some(block_size) = self.block_size {
+            format!("blocks of {} bytes", block_size.unit)
+        } else {
+            match self.human {
+                Some(HumanSize::Binary) => "human-readable, powers of 1024".to_string(),
+                Some(HumanSize::Si) => "human-readable, powers of 1000".to_string(),
+                None => format!("{} suffixes", named(&self.size_style)),
+            }
+        };
+        let measure = if self.actual_size {
+            "allocated"
+        } else {
+            "apparent"
+        };
+        let dirs = if self.dir_sizes {
+            ", directories by contents"
+        } else {
+            ""
+        };
+        lines.push(format!("sizes: {units}, {measure}{dirs}"));
+        lines.push(format!(
+            "color: {}, {} theme, names by {}{}",
+            named(&self.color),
+            named(&self.theme),
+            named(&self.color_by),
+            if self.ls_colors.is_some() {
+                " with LSCOLORS"
+            } else {
+                ""
+            }
+        ));
+        let output = match self.output {
+            Output::Text => "text",
+            Output::Yaml => "YAML",
+            Output::Toml => "TOML",
+            Output::JsonLines => "JSON lines",
+            Output::Shell => "shell words",
+            Output::Json => "JSON",
+            Output::Count => "count only",
+        };
+        lines.push(format!("output: {output}"));
+        lines
+    }
+}
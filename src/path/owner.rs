@@ -0,0 +1,95 @@
+use std::collections::HashMap;
+
+/// Owner names already resolved during a listing. Looking a name up goes
+/// through NSS, which may read /etc/passwd or ask a directory service, and a
+/// directory of thousands of files usually has a handful of owners, so each
+/// id is resolved once and remembered.
+#[derive(Debug, Default)]
+pub struct Owners {
+    names: HashMap<u32, String>,
+    lookups: usize,
+}
+
+impl Owners {
+    /// The user name for `uid`, or the number itself when it has none.
+    pub fn name(&mut self, uid: u32) -> String {
+        if let Some(name) = self.names.get(&uid) {
+            return name.clone();
+        }
+        self.lookups += 1;
+        let name = lookup(uid).unwrap_or_else(|| uid.to_string());
+        self.names.insert(uid, name.clone());
+        name
+    }
+
+    /// How many times the system was actually asked.
+    pub fn lookups(&self) -> usize {
+        self.lookups
+    }
+}
+
+/// Goes through `getpwuid_r`, since listings may look owners up from
+/// several threads and `getpwuid` shares one buffer between all of them.
+#[cfg(unix)]
+fn lookup(uid: u32) -> Option<String> {
+    use std::ffi::CStr;
+
+    let mut buffer = vec![0 as libc::c_char; 1024];
+    loop {
+        // SAFETY: getpwuid_r only writes into the passwd struct and buffer
+        // we hand it, and on success `result` points at that struct, whose
+        // strings live in `buffer`; the name is copied out before either
+        // goes away.
+        unsafe {
+            let mut passwd: libc::passwd = std::mem::zeroed();
+            let mut result = std::ptr::null_mut();
+            let code = libc::getpwuid_r(
+                uid,
+                &mut passwd,
+                buffer.as_mut_ptr(),
+                buffer.len(),
+                &mut result,
+            );
+            if code == libc::ERANGE && buffer.len() < 1 << 20 {
+                buffer.resize(buffer.len() * 2, 0);
+                continue;
+            }
+            if code != 0 || result.is_null() || passwd.pw_name.is_null() {
+                return None;
+            }
+            return Some(
+                CStr::from_ptr(passwd.pw_name)
+                    .to_string_lossy()
+                    .into_owned(),
+            );
+        }
+    }
+}
+
+#[cfg(not(unix))]
+fn lookup(_uid: u32) -> Option<String> {
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn repeated_ids_should_be_looked_up_once() {
+        let mut owners = Owners::default();
+
+        let names: Vec<_> = (0..1000).map(|i| owners.name(i % 2)).collect();
+
+        assert_eq!(owners.lookups(), 2);
+        assert_eq!(names[0], names[998]);
+        assert_eq!(names[1], names[999]);
+    }
+
+    #[test]
+    fn unknown_ids_should_fall_back_to_the_number() {
+        let mut owners = Owners::default();
+
+        assert_eq!(owners.name(4_000_000_000), "4000000000");
+    }
+}
@@ -1,322 +1,2924 @@
-use chrono::{DateTime, Local, NaiveDateTime, Offset, TimeZone};
-use colored::Colorize;
+use super::{
+    dedup::Copies,
+    diff::Change,
+    layout,
+    listing::EntryError,
+    lscolors::Slot,
+    natural,
+    options::{
+        Align, Arrange, ColorBy, Column, Dedup, FileKind, Indicators, NameStyle, Options, Output,
+        SortKey,
+    },
+    owner::Owners,
+    record::{self, Record},
+    shuffle,
+    size::{self, HumanSize, SizeStyle, GIGABYTE, KILOBYTE, MEGABYTE, TERABYTE},
+    theme::Theme,
+    time::{self, Age, Precision, TimeKind, TimeStyle},
+    usage::Usage,
+};
+use chrono::{DateTime, Local};
+use colored::{ColoredString, Colorize};
 use std::{
-    fmt::Error,
-    fs::DirEntry,
-    time::{SystemTime, UNIX_EPOCH},
+    ffi::OsStr,
+    fs::{self, DirEntry, FileType, Metadata},
+    io::{self, Write},
+    path::PathBuf,
+    time::SystemTime,
 };
 
-const KYLOBYTE: u64 = 1000;
-const MEGABYTE: u64 = 1000 * KYLOBYTE;
-const GIGABYTE: u64 = 1000 * MEGABYTE;
-const TERABYTE: u64 = 1000 * GIGABYTE;
-
-#[derive(Debug, PartialEq, Clone)]
+#[derive(Debug, PartialEq, Clone, Default)]
 pub struct Path {
     file_name: String,
+    full_path: PathBuf,
+    /// The directory argument the entry came from, in a merged listing.
+    source: Option<String>,
     is_dir: bool,
+    is_symlink: bool,
+    kind: FileKind,
+    executable: bool,
+    permissions: String,
+    links: String,
+    owner: String,
+    hidden_attribute: bool,
+    bytes: Option<u64>,
+    allocated: Option<u64>,
+    blocks: String,
+    /// Allocated over apparent size, for `Column::Frag`.
+    frag: String,
+    extension: String,
+    xattrs: Vec<(String, usize)>,
     size: String,
+    modified: Option<SystemTime>,
+    accessed: Option<SystemTime>,
+    changed: Option<SystemTime>,
     time: String,
+    children: Option<String>,
+    link_target: Option<String>,
+    broken: bool,
+    /// What a working symlink leads to, for `classify_targets`.
+    target_is_dir: bool,
+    target_executable: bool,
+    looped: bool,
+    /// How the entry changed since the last refresh, with `--watch --diff`.
+    change: Option<Change>,
+    branch: String,
+    indicator: &'static str,
+    /// Shown after the type marker, like ` [3]` from `--child-count`. It
+    /// isn't part of the name, so naming and capping it leave it alone.
+    note: String,
+    bar: String,
     print_string: String,
 }
 
 impl Path {
-    pub fn print(&self) {
-        println!("{}", self.print_string);
+    /// The entry's own metadata is used, so symlinks are not followed.
+    pub fn new(paths: DirEntry) -> io::Result<Self> {
+        Path::with_owners(paths, &mut Owners::default())
     }
 
-    pub fn new(paths: DirEntry) -> Self {
-        let metadata = paths.metadata().unwrap();
-        let file_name = paths.file_name().into_string().unwrap();
-        let is_dir = metadata.is_dir();
-        let size = metadata.len();
-        Path {
+    /// Like `new`, resolving the owner through a cache shared by the listing.
+    pub fn with_owners(paths: DirEntry, owners: &mut Owners) -> io::Result<Self> {
+        let metadata = paths.metadata()?;
+        let file_name = paths.file_name().to_string_lossy().into_owned();
+        Path::from_metadata(file_name, paths.path(), &metadata, owners)
+    }
+
+    /// Builds an entry for a path named on the command line, keeping the name
+    /// exactly as it was given. With `follow`, a symlink is described by what
+    /// it points to, unless that is gone.
+    pub fn from_file(path: &std::path::Path, follow: bool) -> io::Result<Self> {
+        let metadata = if follow {
+            fs::metadata(path).or_else(|_| fs::symlink_metadata(path))?
+        } else {
+            fs::symlink_metadata(path)?
+        };
+        Path::from_metadata(
+            path.to_string_lossy().into_owned(),
+            path.to_path_buf(),
+            &metadata,
+            &mut Owners::default(),
+        )
+    }
+
+    fn from_metadata(
+        file_name: String,
+        full_path: PathBuf,
+        metadata: &Metadata,
+        owners: &mut Owners,
+    ) -> io::Result<Self> {
+        let path = Path {
             file_name,
-            is_dir,
-            size: Path::size_string_formatter(size),
-            time: Path::set_time(metadata.modified().unwrap()).unwrap(),
+            full_path,
+            source: None,
+            is_dir: metadata.is_dir(),
+            is_symlink: metadata.file_type().is_symlink(),
+            kind: FileKind::of(metadata.file_type()),
+            executable: Path::is_executable(metadata),
+            permissions: Path::permissions_string(metadata),
+            links: Path::links_string(metadata),
+            owner: Path::owner_string(metadata, owners),
+            hidden_attribute: Path::hidden_attribute(metadata),
+            bytes: Some(metadata.len()),
+            allocated: Path::allocated_bytes(metadata),
+            blocks: String::new(),
+            frag: String::new(),
+            extension: String::new(),
+            xattrs: Vec::new(),
+            size: String::new(),
+            modified: Some(metadata.modified()?),
+            accessed: metadata.accessed().ok(),
+            changed: Path::changed_time(metadata),
+            time: String::new(),
+            children: None,
+            link_target: None,
+            broken: false,
+            target_is_dir: false,
+            target_executable: false,
+            looped: false,
+            change: None,
+            branch: String::new(),
+            indicator: "",
+            note: String::new(),
+            bar: String::new(),
             print_string: String::new(),
+        };
+        Ok(path.resolve_link())
+    }
+
+    /// Builds an entry from the directory's file type alone, skipping the
+    /// full stat. Only the name and type are known, and for a symlink where
+    /// it points.
+    pub fn from_file_type(full_path: PathBuf, file_type: Option<FileType>) -> Self {
+        Path::from_file_type_alone(full_path, file_type).resolve_link()
+    }
+
+    /// Like `from_file_type` without even reading symlinks, for
+    /// `--names-only`, so a dangling link is no different from any other.
+    pub fn from_file_type_alone(full_path: PathBuf, file_type: Option<FileType>) -> Self {
+        Path {
+            file_name: full_path
+                .file_name()
+                .unwrap_or(full_path.as_os_str())
+                .to_string_lossy()
+                .into_owned(),
+            full_path,
+            is_dir: file_type.is_some_and(|t| t.is_dir()),
+            is_symlink: file_type.is_some_and(|t| t.is_symlink()),
+            kind: file_type.map(FileKind::of).unwrap_or_default(),
+            ..Default::default()
         }
     }
 
-    fn size_string_formatter(size: u64) -> String {
-        if size == 0 {
-            "-".to_string()
-        } else if size < KYLOBYTE {
-            format!("{size}B")
-        } else if size < MEGABYTE {
-            format!("{}KB", size / KYLOBYTE)
-        } else if size < GIGABYTE {
-            format!("{}MB", size / MEGABYTE)
-        } else if size < TERABYTE {
-            format!("{}GB", size / GIGABYTE)
+    /// Reads where a symlink points and whether that target exists. A
+    /// dangling link is still a perfectly listable entry.
+    fn resolve_link(mut self) -> Self {
+        if self.is_symlink {
+            self.link_target = fs::read_link(&self.full_path)
+                .ok()
+                .map(|target| target.to_string_lossy().into_owned());
+            match fs::metadata(&self.full_path) {
+                Ok(target) => {
+                    self.target_is_dir = target.is_dir();
+                    self.target_executable = Path::is_executable(&target);
+                }
+                Err(_) => self.broken = true,
+            }
+        }
+        self
+    }
+
+    /// `name` in the color the `color_by` strategy gives this entry. A
+    /// change `--watch --diff` found is what matters most, so it goes first.
+    fn name_color(&self, name: &str, options: &Options) -> ColoredString {
+        let theme = options.theme;
+        if let Some(change) = self.change {
+            return theme.change(name, change);
+        }
+        match options.color_by {
+            ColorBy::Type => match (&options.ls_colors, self.ls_slot()) {
+                (Some(colors), Some(slot)) if !self.broken => colors.paint(name, slot),
+                _ => self.type_color(name, theme),
+            },
+            ColorBy::Extension => self.extension_color(name, theme),
+            ColorBy::Age => self.age_color(name, theme),
+            ColorBy::None => name.normal(),
+        }
+    }
+
+    /// Which `LSCOLORS` pair colors the entry; plain files have none. The
+    /// set-id and shared-directory pairs need mode bits entries don't keep,
+    /// so those entries go by their type alone.
+    fn ls_slot(&self) -> Option<Slot> {
+        match self.kind {
+            FileKind::Dir => Some(Slot::Directory),
+            FileKind::Symlink => Some(Slot::Symlink),
+            FileKind::Socket => Some(Slot::Socket),
+            FileKind::Fifo => Some(Slot::Pipe),
+            FileKind::Block => Some(Slot::BlockDevice),
+            FileKind::Char => Some(Slot::CharDevice),
+            FileKind::File if self.executable => Some(Slot::Executable),
+            FileKind::File => None,
+        }
+    }
+
+    fn type_color(&self, name: &str, theme: Theme) -> ColoredString {
+        if self.broken {
+            theme.broken(name)
+        } else if self.is_dir {
+            theme.directory(name)
         } else {
-            format!("{}TB", size / TERABYTE)
+            theme.file(name)
         }
     }
 
-    fn set_time(sys_time: SystemTime) -> Result<String, Error> {
-        if let Ok(duration) = sys_time.duration_since(UNIX_EPOCH) {
-            if let Some(time) = NaiveDateTime::from_timestamp_millis(duration.as_millis() as i64) {
-                let local_offset = Local.timestamp_nanos(time.timestamp_nanos()).offset().fix();
-                let local_date_time = DateTime::<Local>::from_utc(time, local_offset);
-                Ok(local_date_time.format("%e %b %R").to_string())
-            } else {
-                panic!("Could not get time from milliseconds")
-            }
+    /// Taken from the path rather than the shown name, which may carry a
+    /// child count or be cut short.
+    fn extension_color(&self, name: &str, theme: Theme) -> ColoredString {
+        match self.full_path.extension() {
+            Some(extension) if !self.is_dir => theme.extension(name, &extension.to_string_lossy()),
+            _ => self.type_color(name, theme),
+        }
+    }
+
+    fn age_color(&self, name: &str, theme: Theme) -> ColoredString {
+        match self.modified {
+            Some(modified) => name.color(Age::of(modified, SystemTime::now()).color()),
+            None => theme.file(name),
+        }
+    }
+
+    pub fn is_dir(&self) -> bool {
+        self.is_dir
+    }
+
+    pub fn is_symlink(&self) -> bool {
+        self.is_symlink
+    }
+
+    pub fn full_path(&self) -> &std::path::Path {
+        &self.full_path
+    }
+
+    /// The name as it was read, before the listing formats it.
+    pub fn name(&self) -> &str {
+        &self.file_name
+    }
+
+    /// The name as the filesystem spells it. Paths built from an entry join
+    /// this rather than the shown name, which is lossy and may be one Windows
+    /// would read as a device (`CON`) or trim (`name. `) if reparsed.
+    pub fn raw_name(&self) -> &OsStr {
+        self.full_path
+            .file_name()
+            .unwrap_or(OsStr::new(&self.file_name))
+    }
+
+    /// Shows the entry as `name` instead of the name it was read with.
+    pub fn set_name(&mut self, name: String) {
+        self.file_name = name;
+    }
+
+    pub fn change(&self) -> Option<Change> {
+        self.change
+    }
+
+    pub fn set_change(&mut self, change: Change) {
+        self.change = Some(change);
+    }
+
+    /// Whether the entry's size or modification time moved on since it was
+    /// read as `earlier`.
+    pub fn differs_from(&self, earlier: &Path) -> bool {
+        self.bytes != earlier.bytes || self.modified != earlier.modified
+    }
+
+    /// Lines drawn before the name to place the entry in a tree.
+    pub fn set_branch(&mut self, branch: String) {
+        self.branch = branch;
+    }
+
+    /// Columns the name takes along with its type marker.
+    fn name_width(&self) -> usize {
+        layout::width(&self.file_name) + self.marks_width()
+    }
+
+    /// The type marker and the note that follow the name.
+    fn marks(&self) -> String {
+        format!("{}{}", self.indicator, self.note)
+    }
+
+    fn marks_width(&self) -> usize {
+        self.indicator.len() + layout::width(&self.note)
+    }
+
+    /// The block count in front of the entry with `size_blocks`, padded and
+    /// followed by `separator`, or nothing.
+    fn blocks_prefix(&self, separator: &str) -> String {
+        if self.blocks.is_empty() {
+            String::new()
         } else {
-            panic!("Time must gone backwards!")
+            format!("{}{separator}", self.blocks)
         }
     }
-}
 
-#[derive(Debug, Default)]
-pub struct Paths {
-    pub paths: Vec<Path>,
-    pub long: bool,
-    pub all: bool,
-    pub tree: (bool, String),
-}
+    /// Where a symlink points for `Column::Target`, or `-`.
+    fn target_cell(&self) -> &str {
+        self.link_target.as_deref().unwrap_or("-")
+    }
 
-impl Paths {
-    fn get_biggest_str_len(&mut self) -> (usize, usize) {
-        let (mut start_len_name, mut start_size_len) = (0, 0);
-        for path in self.paths.iter_mut() {
-            if path.file_name.len() > start_len_name {
-                start_len_name = path.file_name.len();
-            }
-            if path.size.len() > start_size_len {
-                start_size_len = path.size.len();
-            }
+    /// ` -> target` for symlinks in long mode, flagged when the target is gone
+    /// or a recursive walk found it to be a loop.
+    fn link_suffix(&self) -> String {
+        match (&self.link_target, self.broken) {
+            (Some(target), _) if self.looped => format!(" -> {target} (loop)"),
+            (Some(target), false) => format!(" -> {target}"),
+            (Some(target), true) => format!(" -> {target} (broken)"),
+            (None, _) => String::new(),
         }
-        (start_len_name, start_size_len)
     }
 
-    fn indentate_paths(&mut self) {
-        let (biggest_name_len, biggest_size_len) = self.get_biggest_str_len();
-        for path in self.paths.iter_mut() {
-            let spaces_to_add = biggest_name_len - path.file_name.len();
-            for _ in 0..spaces_to_add + 1 {
-                path.file_name.push(' ');
-            }
-            let spaces_to_add = biggest_size_len - path.size.len();
-            for _ in 0..spaces_to_add + 1 {
-                path.size.push(' ');
-            }
+    /// Builds an entry found inside an archive. Directories are the names
+    /// ending in `/`, which is dropped for display. The path goes below the
+    /// archive's own, so it never names a real file by accident.
+    pub fn from_archive_entry(
+        archive: &std::path::Path,
+        name: &str,
+        bytes: u64,
+        mode: Option<u32>,
+        modified: Option<SystemTime>,
+    ) -> Self {
+        let is_dir = name.ends_with('/');
+        let kind = if is_dir { 'd' } else { '-' };
+        Path {
+            file_name: name.trim_end_matches('/').to_string(),
+            full_path: archive.join(name.trim_start_matches('/')),
+            is_dir,
+            executable: !is_dir && mode.is_some_and(|mode| mode & 0o111 != 0),
+            permissions: mode.map_or_else(
+                || format!("{kind}?????????"),
+                |mode| Path::mode_string(kind, mode),
+            ),
+            links: "-".to_string(),
+            owner: "-".to_string(),
+            bytes: Some(bytes),
+            modified,
+            ..Default::default()
         }
     }
 
-    pub fn print(mut self) {
-        self.indentate_paths();
-        self.print_constructor();
-        for path in self.paths.into_iter() {
-            path.print();
+    /// Stand-in for an entry that showed up in its directory but could not be
+    /// statted, e.g. because it was deleted in between. The type comes from
+    /// the directory entry itself when available.
+    pub fn unreadable(full_path: PathBuf, file_type: Option<FileType>) -> Self {
+        let path = Path::from_file_type(full_path, file_type);
+        Path {
+            permissions: format!("{}?????????", path.kind.letter()),
+            links: "?".to_string(),
+            owner: "?".to_string(),
+            time: "?".to_string(),
+            ..path
         }
     }
 
-    fn print_constructor(&mut self) {
-        if !self.all {
-            self.paths
-                .retain_mut(|path| !path.file_name.starts_with('.'));
+    pub(crate) fn size_string_formatter(size: u64, options: &Options) -> String {
+        let (amount, unit) = if options.raw_bytes {
+            return size.to_string();
+        } else if let Some(block_size) = options.block_size {
+            return block_size.format(size);
+        } else if let Some(human) = options.human {
+            return human.format(size);
+        } else if size < KILOBYTE {
+            return format!("{size}B");
+        } else if size < MEGABYTE {
+            (size / KILOBYTE, "KB")
+        } else if size < GIGABYTE {
+            (size / MEGABYTE, "MB")
+        } else if size < TERABYTE {
+            (size / GIGABYTE, "GB")
+        } else {
+            (size / TERABYTE, "TB")
+        };
+        match options.size_style {
+            SizeStyle::Long => format!("{amount}{unit}"),
+            SizeStyle::Short => format!("{amount}{}", &unit[..1]),
         }
+    }
 
-        if self.long && !self.tree.0 {
-            self.paths.iter_mut().for_each(|path| {
-                let mut size_color = path.size.white();
-                let file_name_color: colored::ColoredString = if path.is_dir {
-                    path.file_name.blue()
-                } else {
-                    size_color = path.size.yellow();
-                    path.file_name.white()
-                };
-                path.print_string = format!(
-                    "{} {} {}",
-                    size_color,
-                    path.time.bright_cyan(),
-                    file_name_color
-                );
-            });
-        } else if self.long && self.tree.0 {
-            todo!()
-        } else if !self.long && self.tree.0 {
-            todo!()
+    /// The exact byte count followed by the human-readable size, like
+    /// `1048576 (1.0M)`, in powers of 1024 unless `human` picks 1000. Below
+    /// one unit both would read the same, so the count stands alone.
+    fn size_both(size: u64, options: &Options) -> String {
+        let human = options.human.unwrap_or(HumanSize::Binary).format(size);
+        if human == size.to_string() {
+            human
         } else {
-            self.paths.iter_mut().for_each(|path| {
-                let file_name_color: colored::ColoredString = if path.is_dir {
-                    path.file_name.blue()
-                } else {
-                    path.file_name.white()
-                };
-                path.print_string = format!("{file_name_color}");
-            });
+            format!("{size} ({human})")
         }
     }
 
-    pub fn setup_args(&mut self, args: (bool, bool, Option<String>)) {
-        let (all, long, tree) = args;
-        self.all = all;
-        self.long = long;
-        if let Some(tree) = tree {
-            self.tree = (true, tree);
+    pub(crate) fn record(&self) -> Record {
+        let kind = if self.is_symlink {
+            "symlink"
+        } else if self.is_dir {
+            "directory"
+        } else {
+            "file"
+        };
+        Record {
+            name: self.file_name.clone(),
+            source: self.source.clone(),
+            kind,
+            size: self.bytes,
+            modified: self
+                .modified
+                .map(|modified| DateTime::<Local>::from(modified).to_rfc3339()),
         }
     }
-}
 
-#[cfg(test)]
-mod tests {
-    use std::time::Duration;
+    /// Names of the entry's extended attributes with the length of each
+    /// value, not following symlinks. Anything that keeps them from being
+    /// read, like a filesystem without them, just gives none.
+    #[cfg(unix)]
+    fn read_xattrs(&self) -> Vec<(String, usize)> {
+        let Ok(names) = xattr::list(&self.full_path) else {
+            return Vec::new();
+        };
+        names
+            .map(|name| {
+                let length = xattr::get(&self.full_path, &name)
+                    .ok()
+                    .flatten()
+                    .map_or(0, |value| value.len());
+                (name.to_string_lossy().into_owned(), length)
+            })
+            .collect()
+    }
 
-    use super::*;
+    #[cfg(not(unix))]
+    fn read_xattrs(&self) -> Vec<(String, usize)> {
+        Vec::new()
+    }
 
-    #[test]
-    fn size_string_formatter_less_than_1_kb() {
-        assert_eq!(Path::size_string_formatter(495), "495B");
+    /// Continuation lines under the entry, one per extended attribute, e.g.
+    /// `    user.comment  12`.
+    fn xattr_lines(&self) -> impl Iterator<Item = String> + '_ {
+        let width = self
+            .xattrs
+            .iter()
+            .map(|(name, _)| name.len())
+            .max()
+            .unwrap_or(0);
+        self.xattrs
+            .iter()
+            .map(move |(name, length)| format!("    {name:<width$}  {length}"))
     }
 
-    #[test]
-    fn size_string_formatter_exactly_1_kb() {
-        assert_eq!(Path::size_string_formatter(1000), "1KB");
+    /// When the inode last changed, which only Unix keeps.
+    #[cfg(unix)]
+    fn changed_time(metadata: &Metadata) -> Option<SystemTime> {
+        use std::os::unix::fs::MetadataExt;
+
+        let nanos = std::time::Duration::from_nanos(metadata.ctime_nsec().try_into().ok()?);
+        let since_epoch = std::time::Duration::from_secs(metadata.ctime().try_into().ok()?);
+        Some(SystemTime::UNIX_EPOCH + since_epoch + nanos)
     }
 
-    #[test]
-    fn size_string_formatter_less_than_1_tb() {
-        assert_eq!(Path::size_string_formatter(299392942), "299MB");
+    #[cfg(not(unix))]
+    fn changed_time(_metadata: &Metadata) -> Option<SystemTime> {
+        None
     }
 
-    #[test]
-    fn size_string_formatter_exactly_1_tb() {
-        assert_eq!(Path::size_string_formatter(1000000000000), "1TB");
+    /// The timestamp of the given kind, as far as it is known.
+    fn time_of(&self, kind: TimeKind) -> Option<SystemTime> {
+        match kind {
+            TimeKind::Modification => self.modified,
+            TimeKind::Access => self.accessed,
+            TimeKind::Change => self.changed,
+        }
     }
 
-    #[test]
-    fn size_string_formatter_more_than_1_tb() {
-        assert_eq!(Path::size_string_formatter(293380504804052), "293TB");
+    /// Dotfiles are hidden everywhere, and on Windows so is anything carrying
+    /// the hidden attribute.
+    fn is_hidden(&self) -> bool {
+        self.file_name.starts_with('.') || self.hidden_attribute
     }
 
-    #[test]
-    fn names_should_have_same_length() {
-        let path1 = Path {
-            file_name: "test".to_owned(),
-            is_dir: false,
-            size: "1kb".to_owned(),
-            time: "test".to_owned(),
-            print_string: "print".to_owned(),
-        };
-        let path2 = Path {
-            file_name: "test_test".to_owned(),
-            is_dir: false,
-            size: "1kb".to_owned(),
-            time: "test".to_owned(),
-            print_string: "print".to_owned(),
-        };
-        let mut paths = Paths::default();
-        paths.paths.push(path1);
-        paths.paths.push(path2);
+    #[cfg(windows)]
+    fn hidden_attribute(metadata: &Metadata) -> bool {
+        use std::os::windows::fs::MetadataExt;
+        const FILE_ATTRIBUTE_HIDDEN: u32 = 0x2;
 
-        paths.indentate_paths();
+        metadata.file_attributes() & FILE_ATTRIBUTE_HIDDEN != 0
+    }
 
-        assert_eq!(
-            paths.paths.get(0).unwrap().file_name.len(),
-            paths.paths.get(1).unwrap().file_name.len(),
-        );
+    #[cfg(not(windows))]
+    fn hidden_attribute(_metadata: &Metadata) -> bool {
+        false
     }
 
-    #[test]
-    fn size_should_have_same_length() {
-        let path1 = Path {
-            file_name: "test".to_owned(),
-            is_dir: false,
-            size: "1kb".to_owned(),
-            time: "test".to_owned(),
-            print_string: "print".to_owned(),
-        };
-        let path2 = Path {
-            file_name: "test_test".to_owned(),
-            is_dir: false,
-            size: "1kb".to_owned(),
-            time: "test".to_owned(),
-            print_string: "print".to_owned(),
+    #[cfg(unix)]
+    fn permissions_string(metadata: &Metadata) -> String {
+        use std::os::unix::fs::PermissionsExt;
+
+        let kind = FileKind::of(metadata.file_type()).letter();
+        Path::mode_string(kind, metadata.permissions().mode())
+    }
+
+    /// `ls`-style `drwxr-xr-x` for a file kind character and Unix mode bits.
+    fn mode_string(kind: char, mode: u32) -> String {
+        let mut permissions = String::from(kind);
+        for (bit, c) in [
+            (0o400, 'r'),
+            (0o200, 'w'),
+            (0o100, 'x'),
+            (0o040, 'r'),
+            (0o020, 'w'),
+            (0o010, 'x'),
+            (0o004, 'r'),
+            (0o002, 'w'),
+            (0o001, 'x'),
+        ] {
+            permissions.push(if mode & bit != 0 { c } else { '-' });
+        }
+        permissions
+    }
+
+    #[cfg(not(unix))]
+    fn permissions_string(metadata: &Metadata) -> String {
+        let kind = if metadata.is_dir() { 'd' } else { '-' };
+        let write = if metadata.permissions().readonly() {
+            '-'
+        } else {
+            'w'
         };
-        let mut paths = Paths::default();
-        paths.paths.push(path1);
-        paths.paths.push(path2);
+        format!("{kind}r{write}")
+    }
 
-        paths.indentate_paths();
+    /// Bytes actually taken on disk, which for a sparse file can be far less
+    /// than its length. `st_blocks` is always in 512-byte units.
+    #[cfg(unix)]
+    fn allocated_bytes(metadata: &Metadata) -> Option<u64> {
+        use std::os::unix::fs::MetadataExt;
 
-        assert_eq!(
-            paths.paths.get(0).unwrap().size.len(),
-            paths.paths.get(1).unwrap().size.len(),
-        );
+        Some(metadata.blocks() * 512)
     }
 
-    #[test]
-    fn date_format_should_be_corret() {
-        // Mon Jan 30 2023 20:37:54 UTC+0
-        let time = SystemTime::UNIX_EPOCH + Duration::from_millis(1675111074521);
-        let time_formatted = Path::set_time(time).unwrap();
+    #[cfg(not(unix))]
+    fn allocated_bytes(_metadata: &Metadata) -> Option<u64> {
+        None
+    }
 
-        assert_eq!(time_formatted, "30 Jan 20:37")
+    /// The size to show: the allocated one with `actual`, as far as it is
+    /// known, else the apparent length.
+    fn shown_bytes(&self, actual: bool) -> Option<u64> {
+        if actual {
+            self.allocated.or(self.bytes)
+        } else {
+            self.bytes
+        }
     }
 
-    #[test]
-    fn setup_args_should_setup() {
-        let mut paths = Paths::default();
-        let all = true;
-        let long = true;
-        let tree = Some("dir".to_owned());
+    /// A regular file with any of the execute bits set.
+    #[cfg(unix)]
+    fn is_executable(metadata: &Metadata) -> bool {
+        use std::os::unix::fs::PermissionsExt;
+
+        metadata.is_file() && metadata.permissions().mode() & 0o111 != 0
+    }
+
+    #[cfg(not(unix))]
+    fn is_executable(_metadata: &Metadata) -> bool {
+        false
+    }
 
-        paths.setup_args((all, long, tree));
+    #[cfg(unix)]
+    fn links_string(metadata: &Metadata) -> String {
+        use std::os::unix::fs::MetadataExt;
 
-        assert_eq!(paths.all, all);
-        assert_eq!(paths.long, long);
-        assert_eq!(paths.tree, (true, "dir".to_owned()));
+        metadata.nlink().to_string()
     }
 
-    #[test]
-    fn no_all_argument_should_not_print_dot_files() {
-        let mut paths = Paths::default();
-        let all = false;
-        let long = false;
-        let tree = None;
-        paths.setup_args((all, long, tree));
+    #[cfg(not(unix))]
+    fn links_string(_metadata: &Metadata) -> String {
+        "1".to_string()
+    }
 
-        let mut path1 = Path {
-            file_name: ".test".to_owned(),
-            is_dir: false,
-            size: "1kb".to_owned(),
-            time: "test".to_owned(),
-            print_string: "print".to_owned(),
-        };
+    #[cfg(unix)]
+    fn owner_string(metadata: &Metadata, owners: &mut Owners) -> String {
+        use std::os::unix::fs::MetadataExt;
 
-        paths.paths.push(path1.clone());
-        paths.print_constructor();
-        // This is need as this is the result of any string using the Colored white fn
-        path1.print_string = "\u{1b}[37m.test\u{1b}[0m".to_owned();
+        owners.name(metadata.uid())
+    }
 
-        assert!(!paths.paths.contains(&path1));
+    #[cfg(not(unix))]
+    fn owner_string(_metadata: &Metadata, _owners: &mut Owners) -> String {
+        "-".to_string()
     }
 
-    #[test]
-    fn all_argument_should_print_dot_files() {
-        let mut paths = Paths::default();
-        let all = true;
-        let long = false;
-        let tree = None;
-        paths.setup_args((all, long, tree));
+    fn set_time(sys_time: SystemTime, style: TimeStyle, precision: Precision, utc: bool) -> String {
+        time::format(sys_time, style, precision, utc)
+    }
+}
 
-        let mut path1 = Path {
-            file_name: ".test".to_owned(),
-            is_dir: false,
-            size: "1kb".to_owned(),
-            time: "test".to_owned(),
-            print_string: "print".to_owned(),
-        };
+#[derive(Debug, Default)]
+pub struct Paths {
+    pub paths: Vec<Path>,
+    pub options: Options,
+    pub errors: Vec<EntryError>,
+    /// Set once the columns are aligned, see `indentate_paths`.
+    widths: Option<Widths>,
+}
 
-        paths.paths.push(path1.clone());
-        paths.print_constructor();
-        // This is need as this is the result of any string using the Colored white fn
-        path1.print_string = "\u{1b}[37m.test\u{1b}[0m".to_owned();
+/// How wide the columns padded with trailing spaces are. The spaces are
+/// only added when a row is put together, and only before another cell,
+/// so they never end up in (or get confused with) the text itself.
+#[derive(Debug, Clone, Copy)]
+struct Widths {
+    name: usize,
+    size: usize,
+    size_align: Align,
+    owner: usize,
+    extension: usize,
+    frag: usize,
+    target: usize,
+}
 
-        assert!(paths.paths.contains(&path1));
+impl Paths {
+    pub fn with_options(options: Options) -> Self {
+        Paths {
+            options,
+            ..Default::default()
+        }
+    }
+
+    /// Pools several listings into one, for `--merge`. Each is filtered the
+    /// way its own directory asks, e.g. by its `.lsrignore`, and its entries
+    /// remember which argument they came from, so two with the same name
+    /// can be told apart.
+    pub fn merge(options: Options, listings: Vec<(String, Paths)>) -> Self {
+        let mut merged = Paths::with_options(options);
+        for (source, listing) in listings {
+            merged
+                .paths
+                .extend(listing.ordered().into_iter().map(|path| Path {
+                    source: Some(source.clone()),
+                    ..path
+                }));
+        }
+        merged
+    }
+
+    fn get_biggest_str_len(&mut self) -> (usize, usize) {
+        let (mut start_len_name, mut start_size_len) = (0, 0);
+        for path in self.paths.iter_mut() {
+            start_len_name = start_len_name.max(path.name_width());
+            start_size_len = start_size_len.max(layout::width(&path.size));
+        }
+        (start_len_name, start_size_len)
+    }
+
+    /// Counts the immediate children of every listed directory, honoring the
+    /// same hidden-file filter as the listing itself. A directory that can't
+    /// be read gets `?`.
+    fn count_children(&mut self) {
+        let all = self.options.all;
+        for path in self.paths.iter_mut().filter(|path| path.is_dir) {
+            let count = Paths::shown_children(&path.full_path, all).map(Iterator::count);
+            let count = count.map_or_else(|_| "?".to_string(), |count| count.to_string());
+            path.note.push_str(&format!(" [{count}]"));
+            path.children = Some(count);
+        }
+    }
+
+    /// Notes `(empty)` after every listed directory with nothing in it to
+    /// show, and `(?)` after one that can't be read. Like the child count it
+    /// goes by the hidden-file filter, so a directory holding only dotfiles
+    /// is empty unless `all` would show them.
+    fn mark_empty(&mut self) {
+        let all = self.options.all;
+        for path in self.paths.iter_mut().filter(|path| path.is_dir) {
+            match Paths::shown_children(&path.full_path, all).map(|mut shown| shown.next()) {
+                Ok(None) => path.note.push_str(" (empty)"),
+                Ok(Some(_)) => {}
+                Err(_) => path.note.push_str(" (?)"),
+            }
+        }
+    }
+
+    /// The entries of `dir` a listing of it would show by name, lazily, so
+    /// looking for the first one reads no further.
+    fn shown_children(
+        dir: &std::path::Path,
+        all: bool,
+    ) -> io::Result<impl Iterator<Item = fs::DirEntry>> {
+        Ok(fs::read_dir(dir)?
+            .filter_map(Result::ok)
+            .filter(move |entry| all || !entry.file_name().to_string_lossy().starts_with('.')))
+    }
+
+    /// Picks each entry's type marker. It is kept apart from the name so the
+    /// name can be colored on its own, while the columns still make room for
+    /// it. In the long format a symlink already shows its target, so it
+    /// needs no `@`. With `classify_targets`, a link to a directory or an
+    /// executable is marked like what it leads to instead; other links, and
+    /// broken ones, keep their `@`.
+    fn classify(&mut self) {
+        let (indicators, long) = (self.options.indicators, self.options.long);
+        let targets = self.options.classify_targets;
+        for path in self.paths.iter_mut() {
+            path.indicator = match indicators {
+                Indicators::None => "",
+                Indicators::Executables | Indicators::Classify if path.executable => "*",
+                Indicators::Executables => "",
+                Indicators::Classify if path.is_symlink => {
+                    if long {
+                        ""
+                    } else if targets && path.target_is_dir {
+                        "/"
+                    } else if targets && path.target_executable {
+                        "*"
+                    } else {
+                        "@"
+                    }
+                }
+                Indicators::Classify if path.is_dir => "/",
+                Indicators::Classify => "",
+            };
+        }
+    }
+
+    /// Splits off what follows the last dot of each file's name, for the
+    /// extension column. Directories and names without one, or with only a
+    /// leading dot like `.bashrc`, get nothing.
+    fn split_extensions(&mut self) {
+        for path in self.paths.iter_mut().filter(|path| !path.is_dir) {
+            path.extension = match path.file_name.rsplit_once('.') {
+                Some((stem, extension)) if !stem.is_empty() => extension.to_string(),
+                _ => String::new(),
+            };
+        }
+    }
+
+    /// Compares the space each file takes on disk with its length: far less
+    /// is a sparse file, with holes that read as zeros, and far more is
+    /// slack, the unused end of its last blocks. Directories, empty files
+    /// and anything whose blocks can't be read, like everywhere but Unix,
+    /// get `-`.
+    fn compare_allocation(&mut self) {
+        for path in self.paths.iter_mut() {
+            path.frag = match (path.bytes, path.allocated) {
+                (Some(apparent), Some(allocated))
+                    if apparent > 0 && !path.is_dir && !path.is_symlink =>
+                {
+                    let ratio = allocated as f64 / apparent as f64;
+                    let hint = if allocated.saturating_mul(2) < apparent {
+                        " sparse"
+                    } else if allocated >= apparent.saturating_mul(2) {
+                        " slack"
+                    } else {
+                        ""
+                    };
+                    format!("{ratio:.2}x{hint}")
+                }
+                _ => "-".to_string(),
+            };
+        }
+    }
+
+    /// Cuts names down to `max` bytes before the columns are measured.
+    fn cap_names(&mut self, max: usize) {
+        for path in self.paths.iter_mut() {
+            path.file_name = layout::truncate_bytes(&path.file_name, max);
+        }
+    }
+
+    /// Turns each name into the one shown, by `name_style` or the source.
+    fn name_paths(&mut self) {
+        // A full path already says where the entry came from
+        if self.options.name_style != NameStyle::Base {
+            self.name_full_paths();
+        } else {
+            self.prefix_sources();
+        }
+    }
+
+    /// Puts the source directory of a merged listing in front of each name.
+    /// Like `name_full_paths`, it runs after sorting, which goes by the name.
+    fn prefix_sources(&mut self) {
+        for path in self.paths.iter_mut() {
+            if let Some(source) = &path.source {
+                let prefixed = std::path::Path::new(source).join(path.raw_name());
+                path.file_name = self.options.shown_path(&prefixed);
+            }
+        }
+    }
+
+    /// Shows each entry by its absolute or canonical path instead of its
+    /// name, or by the path from `relative_to` for entries inside it. It
+    /// runs after filtering and sorting, which go by the name.
+    fn name_full_paths(&mut self) {
+        let canonical = self.options.name_style == NameStyle::Canonical;
+        let base = self.options.relative_to.as_deref();
+        for path in self.paths.iter_mut() {
+            let resolved = canonical
+                .then(|| fs::canonicalize(&path.full_path).ok())
+                .flatten()
+                .or_else(|| std::path::absolute(&path.full_path).ok());
+            let Some(resolved) = resolved else {
+                continue;
+            };
+            // The base has its symlinks resolved, so an absolute path
+            // through one is matched by where it really is
+            let relative = base.and_then(|base| match resolved.strip_prefix(base) {
+                Ok(relative) => Some(relative.to_path_buf()),
+                Err(_) => fs::canonicalize(&path.full_path)
+                    .ok()?
+                    .strip_prefix(base)
+                    .ok()
+                    .map(|relative| relative.to_path_buf()),
+            });
+            path.file_name = match relative {
+                Some(relative) if relative.as_os_str().is_empty() => ".".to_string(),
+                Some(relative) => relative.to_string_lossy().into_owned(),
+                None => self.options.shown_path(&resolved),
+            };
+        }
+    }
+
+    /// Size bars scaled to the biggest file. Directories get blank space of
+    /// the same width so the following columns stay aligned.
+    fn draw_bars(&mut self) {
+        let actual = self.options.actual_size;
+        let max = self
+            .paths
+            .iter()
+            .filter(|path| !path.is_dir)
+            .filter_map(|path| path.shown_bytes(actual))
+            .max()
+            .unwrap_or(0);
+        for path in self.paths.iter_mut() {
+            path.bar = match path.shown_bytes(actual) {
+                Some(bytes) if !path.is_dir => size::bar(bytes, max, self.options.bar_scale),
+                _ => " ".repeat(size::BAR_WIDTH),
+            };
+        }
+    }
+
+    fn format_times(&mut self) {
+        let (style, utc) = (self.options.time_style, self.options.utc);
+        let precision = self.options.time_precision;
+        for path in self.paths.iter_mut() {
+            if let Some(time) = path.time_of(self.options.time_kind) {
+                path.time = Path::set_time(time, style, precision, utc);
+            }
+        }
+    }
+
+    /// Whether the size column gives `path` a size: directories only have
+    /// one when measured or asked for in bytes.
+    fn is_sized(options: &Options, path: &Path) -> bool {
+        !path.is_dir || options.dir_sizes || options.raw_bytes
+    }
+
+    fn format_sizes(&mut self) {
+        for path in self.paths.iter_mut() {
+            // A directory's own length says nothing about what it holds, and
+            // neither does a length of 0 in /proc. Exact byte counts still
+            // show it, so every size is a number.
+            let raw = self.options.raw_bytes;
+            let sized = Paths::is_sized(&self.options, path);
+            path.size = match path.shown_bytes(self.options.actual_size) {
+                None => "?".to_string(),
+                Some(0) if self.options.virtual_fs && !raw => "-".to_string(),
+                Some(_) if !sized => "-".to_string(),
+                Some(bytes) if self.options.size_both => Path::size_both(bytes, &self.options),
+                Some(bytes) => Path::size_string_formatter(bytes, &self.options),
+            };
+        }
+    }
+
+    /// Writes how many blocks each entry takes, like `ls -s`, and returns
+    /// the `total` line for all of them.
+    fn count_blocks(&mut self) -> String {
+        let unit = self.block_unit();
+        for path in self.paths.iter_mut() {
+            path.blocks = match path.allocated {
+                Some(bytes) => bytes.div_ceil(unit).to_string(),
+                None => "?".to_string(),
+            };
+        }
+        self.blocks_total()
+    }
+
+    /// The `total` line `ls -l` heads a listing with: the blocks all entries
+    /// take, in 1024 bytes unless `block_size` says otherwise, each rounded
+    /// up. Entries whose blocks couldn't be read add nothing.
+    fn blocks_total(&self) -> String {
+        let unit = self.block_unit();
+        let total: u64 = self
+            .paths
+            .iter()
+            .filter_map(|path| path.allocated)
+            .map(|bytes| bytes.div_ceil(unit))
+            .sum();
+        format!("total {total}")
+    }
+
+    fn block_unit(&self) -> u64 {
+        self.options
+            .block_size
+            .map_or(1024, |block_size| block_size.unit)
+    }
+
+    /// Replaces each directory's own length with what is stored below it.
+    /// Every directory is measured on its own, so a file hardlinked into two
+    /// of them counts in both.
+    fn measure_dirs(&mut self) {
+        let actual = self.options.actual_size;
+        for path in self
+            .paths
+            .iter_mut()
+            .filter(|path| path.is_dir && !path.is_symlink)
+        {
+            let bytes = Usage::new(actual).of(&path.full_path);
+            path.bytes = Some(bytes);
+            path.allocated = Some(bytes);
+        }
+    }
+
+    fn indentate_paths(&mut self) {
+        // Both sizes at once line up on the byte count, and the human sizes
+        // after it then trail off unevenly
+        let size_align = if self.options.size_both {
+            self.align_byte_counts();
+            Align::Left
+        } else {
+            self.options.size_align
+        };
+        let (mut biggest_name_len, biggest_size_len) = self.get_biggest_str_len();
+        if let Some(fixed) = self.options.name_width {
+            biggest_name_len = fixed;
+            for path in self.paths.iter_mut() {
+                let room = fixed.saturating_sub(path.marks_width());
+                path.file_name = layout::truncate(&path.file_name, room);
+            }
+        }
+        let widest = |cell: fn(&Path) -> &str| {
+            self.paths
+                .iter()
+                .map(|path| layout::width(cell(path)))
+                .max()
+                .unwrap_or(0)
+        };
+        let biggest_links_len = widest(|path| &path.links);
+        let biggest_blocks_len = widest(|path| &path.blocks);
+        self.widths = Some(Widths {
+            name: biggest_name_len,
+            size: biggest_size_len,
+            size_align,
+            owner: widest(|path| &path.owner),
+            extension: widest(|path| &path.extension),
+            frag: widest(|path| &path.frag),
+            target: widest(Path::target_cell),
+        });
+        // Right-aligned numbers only ever get spaces in front
+        for path in self.paths.iter_mut() {
+            Paths::pad(&mut path.links, biggest_links_len, Align::Right);
+            Paths::pad(&mut path.blocks, biggest_blocks_len, Align::Right);
+        }
+    }
+
+    /// Right-aligns the byte counts `size_both` starts each size with.
+    fn align_byte_counts(&mut self) {
+        let count = |size: &str| size.split(' ').next().unwrap_or_default().len();
+        let widest = self.paths.iter().map(|path| count(&path.size)).max();
+        for path in self.paths.iter_mut() {
+            let spaces = " ".repeat(widest.unwrap_or(0) - count(&path.size));
+            path.size.insert_str(0, &spaces);
+        }
+    }
+
+    fn pad(text: &mut String, width: usize, align: Align) {
+        let (before, after) = Paths::padding(layout::width(text), width, align);
+        text.insert_str(0, &before);
+        text.push_str(&after);
+    }
+
+    /// The spaces before and after text `text_width` wide that take it to
+    /// `width`, depending on which side it sticks to.
+    fn padding(text_width: usize, width: usize, align: Align) -> (String, String) {
+        let spaces = " ".repeat(width.saturating_sub(text_width));
+        match align {
+            Align::Left => (String::new(), spaces),
+            Align::Right => (spaces, String::new()),
+        }
+    }
+
+    pub fn print(self) -> io::Result<()> {
+        self.write_to(&mut io::stdout().lock())
+    }
+
+    pub fn write_to(mut self, out: &mut impl Write) -> io::Result<()> {
+        out.write_all(self.build().as_bytes())
+    }
+
+    /// Everything `print` would write, colors included unless they are turned
+    /// off, without touching stdout. The entries themselves are left as they
+    /// are, so this can be called any number of times.
+    pub fn render(&self) -> String {
+        let mut paths = Paths {
+            paths: self.paths.clone(),
+            options: self.options.clone(),
+            errors: Vec::new(),
+            widths: None,
+        };
+        paths.build()
+    }
+
+    /// Runs the whole pipeline (filter, sort, format, align, color) over the
+    /// entries in place and lays out the result.
+    fn build(&mut self) -> String {
+        // A tree arrives filtered and ordered directory by directory. Hidden
+        // entries `count_all` adds to the summaries stay until they're taken.
+        let flat = self.options.tree.is_none();
+        if flat {
+            self.retain_kept(self.options.count_all);
+        }
+        // Measured before sorting, so --sort=size goes by what dirs hold
+        if self.options.dir_sizes {
+            self.measure_dirs();
+        }
+        // Summaries may include entries the listing hides, so take them
+        // before the last filtering, once sizes are settled
+        let mut footer = Vec::new();
+        if self.options.count {
+            footer.push(self.summary());
+        }
+        if self.options.total {
+            footer.push(self.total());
+        }
+        if flat {
+            self.filter_paths();
+            self.sort_paths();
+            // After sorting, so the first of the copies is the one listed first
+            if let Some(dedup) = self.options.dedup {
+                self.filter_copies(dedup);
+            }
+        }
+        if self.options.highlights {
+            footer.extend(self.highlights());
+        }
+        let records = || self.paths.iter().map(Path::record).collect::<Vec<Record>>();
+        match self.options.output {
+            Output::Text => {}
+            Output::Yaml => return record::to_yaml(&records()),
+            Output::Toml => return record::to_toml(&records()),
+            // Without a tree to nest, JSON is a line per entry
+            Output::JsonLines | Output::Json => {
+                return records().iter().map(record::to_json_line).collect()
+            }
+            Output::Count => return format!("{}\n", self.paths.len()),
+            Output::Shell => {
+                self.name_paths();
+                return record::to_shell(self.paths.iter().map(|path| path.file_name.as_str()));
+            }
+        }
+        self.format_sizes();
+        if self.options.bars {
+            self.draw_bars();
+        }
+        self.format_times();
+        if self.options.child_count {
+            self.count_children();
+        }
+        if self.options.mark_empty {
+            self.mark_empty();
+        }
+        let blocks_total = if self.options.size_blocks {
+            Some(self.count_blocks())
+        } else {
+            // Only Unix says how many blocks a file takes
+            (cfg!(unix) && self.options.long && self.options.blocks_total)
+                .then(|| self.blocks_total())
+        };
+        self.classify();
+        if self.options.long && self.options.xattrs {
+            for path in self.paths.iter_mut() {
+                path.xattrs = path.read_xattrs();
+            }
+        }
+        if self.options.long && self.options.columns.contains(&Column::Ext) {
+            self.split_extensions();
+        }
+        if self.options.long && self.options.columns.contains(&Column::Frag) {
+            self.compare_allocation();
+        }
+        self.name_paths();
+        if let Some(max) = self.options.max_name_bytes {
+            self.cap_names(max);
+        }
+        // Without alignment the long format keeps each field as it is
+        if self.options.aligned || !self.options.long {
+            self.indentate_paths();
+        }
+        self.print_constructor();
+
+        let mut lines = if self.paths.is_empty() {
+            if self.options.empty_message {
+                vec!["(empty)".to_string()]
+            } else {
+                Vec::new()
+            }
+        } else if self.options.long || self.options.tree.is_some() {
+            self.paths
+                .iter()
+                .flat_map(|path| {
+                    std::iter::once(path.print_string.clone()).chain(path.xattr_lines())
+                })
+                .collect()
+        } else {
+            match self.options.arrange {
+                // The margin takes up part of each line
+                Arrange::Grid => self.grid_lines(
+                    self.options
+                        .width
+                        .map(|width| width.saturating_sub(self.options.margin)),
+                ),
+                Arrange::Fill => self.filled_lines(""),
+                Arrange::Commas => self.filled_lines(","),
+            }
+        };
+        // Like `ls`, a tree has no place for the total of one level
+        if let Some(total) = blocks_total.filter(|_| flat && !self.paths.is_empty()) {
+            lines.insert(0, total);
+        }
+        if !footer.is_empty() {
+            lines.push(String::new());
+            lines.append(&mut footer);
+        }
+        let margin = " ".repeat(self.options.margin);
+        let end = if self.options.zero { '\0' } else { '\n' };
+        lines
+            .into_iter()
+            .map(|line| {
+                if line.is_empty() {
+                    end.to_string()
+                } else {
+                    format!("{margin}{line}{end}")
+                }
+            })
+            .collect()
+    }
+
+    /// The biggest file and the oldest entry that are listed, e.g.
+    /// `largest: big.bin (2GB)`. Ties go to the first name alphabetically.
+    fn highlights(&self) -> Vec<String> {
+        let actual = self.options.actual_size;
+        let by_name = |a: &Path, b: &Path| b.file_name.cmp(&a.file_name);
+        let largest = self
+            .paths
+            .iter()
+            .filter(|path| !path.is_dir)
+            .filter_map(|path| Some((path.shown_bytes(actual)?, path)))
+            .max_by(|(a_bytes, a), (b_bytes, b)| a_bytes.cmp(b_bytes).then_with(|| by_name(a, b)));
+        let oldest = self
+            .paths
+            .iter()
+            .filter_map(|path| Some((path.modified?, path)))
+            .max_by(|(a_time, a), (b_time, b)| b_time.cmp(a_time).then_with(|| by_name(a, b)));
+
+        let mut lines = Vec::new();
+        if let Some((bytes, path)) = largest {
+            let size = Path::size_string_formatter(bytes, &self.options);
+            lines.push(format!("largest: {} ({size})", path.file_name));
+        }
+        if let Some((modified, path)) = oldest {
+            let date = time::date(modified, self.options.utc);
+            lines.push(format!("oldest: {} ({date})", path.file_name));
+        }
+        lines
+    }
+
+    /// Tally of the listed entries, e.g. `3 directories, 10 files, 2 symlinks`.
+    /// Symlinks are counted on their own, whether or not their target exists.
+    fn summary(&self) -> String {
+        let (mut directories, mut files, mut symlinks) = (0, 0, 0);
+        for path in self.summarized() {
+            if path.is_symlink {
+                symlinks += 1;
+            } else if path.is_dir {
+                directories += 1;
+            } else {
+                files += 1;
+            }
+        }
+        let plural = |count: usize, one: &str, many: &str| {
+            format!("{count} {}", if count == 1 { one } else { many })
+        };
+        format!(
+            "{}, {}, {}",
+            plural(directories, "directory", "directories"),
+            plural(files, "file", "files"),
+            plural(symlinks, "symlink", "symlinks")
+        )
+    }
+
+    /// Combined size of the summarized entries, e.g. `total size: 12KB`.
+    /// Only the sizes the size column shows are added up.
+    fn total(&self) -> String {
+        let actual = self.options.actual_size;
+        let bytes: u64 = self
+            .summarized()
+            .filter(|path| Paths::is_sized(&self.options, path))
+            .filter_map(|path| path.shown_bytes(actual))
+            .sum();
+        format!(
+            "total size: {}",
+            Path::size_string_formatter(bytes, &self.options)
+        )
+    }
+
+    /// Packs the entries into as many columns as fit in `width`, or as many
+    /// as `grid_columns` asks for, filling column by column like `ls` does.
+    /// Without either (output is not a terminal) every entry gets its own
+    /// line.
+    fn grid_lines(&self, width: Option<usize>) -> Vec<String> {
+        let widths = self.widths;
+        let name_align = self.options.name_align;
+        let cells: Vec<(&str, usize)> = self
+            .paths
+            .iter()
+            .map(|path| {
+                // Right-aligned names start with their padding
+                let lead = match (widths, name_align) {
+                    (Some(widths), Align::Right) => widths.name.saturating_sub(path.name_width()),
+                    _ => 0,
+                };
+                let width = lead + path.name_width() + path.blocks_prefix(" ").len();
+                (path.print_string.as_str(), width)
+            })
+            .collect();
+        // Every column is as wide as --name-width when that is set
+        let min = match (self.options.name_width, self.paths.first()) {
+            (Some(fixed), Some(path)) => fixed + path.blocks_prefix(" ").len(),
+            _ => 0,
+        };
+        layout::grid(&cells, width, self.options.grid_columns, 2, min)
+    }
+
+    /// Names run on like a paragraph, for `-m` and `--fill`. Without a
+    /// terminal the lines are as wide as one would be by default.
+    fn filled_lines(&self, separator: &str) -> Vec<String> {
+        let names: Vec<(String, usize)> = self
+            .paths
+            .iter()
+            .map(|path| {
+                let name = &path.file_name;
+                let blocks = path.blocks_prefix(" ");
+                let blocks = blocks.trim_start();
+                let text = format!(
+                    "{blocks}{}{}",
+                    path.name_color(name, &self.options),
+                    path.marks()
+                );
+                (
+                    text,
+                    blocks.len() + layout::width(name) + path.marks_width(),
+                )
+            })
+            .collect();
+        let cells: Vec<(&str, usize)> = names.iter().map(|(text, w)| (text.as_str(), *w)).collect();
+        layout::fill(&cells, self.options.width.unwrap_or(80), separator)
+    }
+
+    fn filter_paths(&mut self) {
+        self.retain_kept(false);
+    }
+
+    /// Drops the entries `keep` turns down, with or without hidden ones.
+    fn retain_kept(&mut self, include_hidden: bool) {
+        let kept: Vec<bool> = self
+            .paths
+            .iter()
+            .map(|path| self.keep(path, include_hidden))
+            .collect();
+        let mut kept = kept.into_iter();
+        self.paths.retain(|_| kept.next().unwrap_or(false));
+    }
+
+    /// Keeps the files `dedup` asks for, reading the content of each one.
+    fn filter_copies(&mut self, dedup: Dedup) {
+        let mut copies = Copies::default();
+        let wanted = dedup == Dedup::CopiesOnly;
+        self.paths
+            .retain(|path| copies.is_copy(&path.full_path) == wanted);
+    }
+
+    /// Whether the listing would show `path`, for entries that are looked
+    /// at one by one rather than gathered into `paths`.
+    pub fn shows(&self, path: &Path) -> bool {
+        self.keep(path, false)
+    }
+
+    /// Whether `path` makes it into the listing. `include_hidden` skips the
+    /// hidden-file rule, for summaries that count everything.
+    fn keep(&self, path: &Path, include_hidden: bool) -> bool {
+        let shown = if include_hidden {
+            true
+        } else if self.options.dotfiles_only {
+            path.is_hidden() && path.file_name != "." && path.file_name != ".."
+        } else {
+            self.options.all || !(path.is_hidden() || self.is_ignored(path))
+        };
+        shown && self.is_kind(path) && self.is_recent(path) && self.is_changed(path)
+    }
+
+    /// Whether `path` is of a type `--type` asks for.
+    fn is_kind(&self, path: &Path) -> bool {
+        let kinds = &self.options.kinds;
+        kinds.is_empty() || kinds.contains(&path.kind)
+    }
+
+    /// Whether `path` passes `--since`, when its directory was read with it.
+    fn is_changed(&self, path: &Path) -> bool {
+        self.options
+            .changed
+            .as_ref()
+            .is_none_or(|changed| changed.contains(&path.file_name))
+    }
+
+    /// Whether `path` passes `--modified-within`. An entry whose time can't
+    /// be read is never recent.
+    fn is_recent(&self, path: &Path) -> bool {
+        self.options
+            .modified_since
+            .is_none_or(|since| path.modified.is_some_and(|modified| modified >= since))
+    }
+
+    /// Whether `path` matches an `--ignore` or `.lsrignore` pattern.
+    fn is_ignored(&self, path: &Path) -> bool {
+        self.options
+            .ignore
+            .iter()
+            .any(|pattern| pattern.matches(&path.file_name))
+    }
+
+    /// Entries the summaries are about: exactly what is listed, unless
+    /// `count_all` asks for hidden files to be included regardless of `-a`.
+    fn summarized(&self) -> impl Iterator<Item = &Path> {
+        self.paths
+            .iter()
+            .filter(|path| self.keep(path, self.options.count_all))
+    }
+
+    /// The entries a listing shows, in the order it shows them.
+    pub fn ordered(mut self) -> Vec<Path> {
+        self.filter_paths();
+        self.sort_paths();
+        self.paths
+    }
+
+    /// Flags the entry at `full_path` as leading back into a directory the
+    /// walk has already been through.
+    pub fn mark_loop(&mut self, full_path: &std::path::Path) {
+        if let Some(path) = self
+            .paths
+            .iter_mut()
+            .find(|path| path.full_path == full_path)
+        {
+            path.looped = true;
+        }
+    }
+
+    /// Directories a recursive listing descends into, in listing order.
+    /// Symlinks are left alone so a link can't send the walk in circles,
+    /// unless `follow_symlinks` asks for links to directories too.
+    pub fn subdirectories(&self) -> Vec<PathBuf> {
+        let follow = self.options.follow_symlinks;
+        let mut dirs = Paths::with_options(self.options.clone());
+        dirs.paths = self
+            .paths
+            .iter()
+            .filter(|path| {
+                if path.is_symlink {
+                    follow && !path.broken && path.full_path.is_dir()
+                } else {
+                    path.is_dir
+                }
+            })
+            .cloned()
+            .collect();
+        dirs.filter_paths();
+        dirs.sort_paths();
+        dirs.paths.into_iter().map(|path| path.full_path).collect()
+    }
+
+    fn sort_paths(&mut self) {
+        let by_name = |a: &Path, b: &Path| a.file_name.cmp(&b.file_name);
+        let actual = self.options.actual_size;
+        match self.options.sort {
+            SortKey::None => {}
+            SortKey::Name => self.paths.sort_by(by_name),
+            SortKey::Size => self.paths.sort_by(|a, b| {
+                let (a_bytes, b_bytes) = (a.shown_bytes(actual), b.shown_bytes(actual));
+                b_bytes.cmp(&a_bytes).then_with(|| by_name(a, b))
+            }),
+            SortKey::Time => self.paths.sort_by(|a, b| {
+                let kind = self.options.time_kind;
+                b.time_of(kind)
+                    .cmp(&a.time_of(kind))
+                    .then_with(|| by_name(a, b))
+            }),
+            SortKey::Length => self.paths.sort_by(|a, b| {
+                let length = |path: &Path| path.file_name.chars().count();
+                length(a).cmp(&length(b)).then_with(|| by_name(a, b))
+            }),
+            SortKey::Version => self
+                .paths
+                .sort_by(|a, b| natural::compare(&a.file_name, &b.file_name)),
+            SortKey::Random => {
+                // From the same order each time, whatever the directory yields
+                self.paths.sort_by(by_name);
+                shuffle::shuffle(&mut self.paths, self.options.seed);
+            }
+        }
+        if self.options.reverse {
+            self.paths.reverse();
+        }
+    }
+
+    fn print_constructor(&mut self) {
+        let now = SystemTime::now();
+        let heatmap = self.options.heatmap;
+        let recent_since = self.options.recent_since;
+        let theme = self.options.theme;
+        let options = &self.options;
+        let time_kind = self.options.time_kind;
+        let actual = self.options.actual_size;
+        let widths = self.widths;
+        let name_align = self.options.name_align;
+        // Spaces before and after text `width` wide in a column it is padded
+        // to, once the columns are aligned
+        let around = |width, column: fn(&Widths) -> usize, align| match widths {
+            Some(widths) => Paths::padding(width, column(&widths), align),
+            None => (String::new(), String::new()),
+        };
+        if self.options.long {
+            let columns = &self.options.columns;
+            let targets = columns.contains(&Column::Target);
+            // The gradient is relative to the biggest file listed
+            let max = self.options.color_scale.then(|| {
+                self.paths
+                    .iter()
+                    .filter(|path| !path.is_dir)
+                    .filter_map(|path| path.shown_bytes(actual))
+                    .max()
+                    .unwrap_or(0)
+            });
+            self.paths.iter_mut().for_each(|path| {
+                let time_color = match path.time_of(time_kind) {
+                    Some(time) if heatmap => path.time.color(Age::of(time, now).color()),
+                    Some(time) if recent_since.is_some_and(|since| time >= since) => {
+                        theme.recent(&path.time)
+                    }
+                    _ => theme.time(&path.time),
+                };
+                let color = |text: &str, bytes| match max {
+                    Some(max) => size::gradient(text, bytes, max),
+                    None => theme.size(text, bytes),
+                };
+                let (size_color, bar_color) = match path.shown_bytes(actual) {
+                    Some(bytes) if !path.is_dir => {
+                        (color(&path.size, bytes), color(&path.bar, bytes))
+                    }
+                    _ => (theme.file(&path.size), theme.file(&path.bar)),
+                };
+                let width = layout::width(&path.size);
+                let size_align = widths.map_or(Align::Left, |widths| widths.size_align);
+                let (before, after) = around(width, |widths| widths.size, size_align);
+                // Aligned sizes stand a space further off what follows them
+                let gap = if widths.is_some() { " " } else { "" };
+                // A blank bar is only padding, and goes when nothing follows
+                let size = if path.bar.is_empty() {
+                    (format!("{before}{size_color}"), format!("{after}{gap}"))
+                } else if path.bar.trim().is_empty() {
+                    let padding = format!("{after}{gap}{} ", path.bar);
+                    (format!("{before}{size_color}"), padding)
+                } else {
+                    let size = format!("{before}{size_color}{after}{gap}{bar_color}");
+                    (size, " ".to_string())
+                };
+                // Each cell with the spaces that pad it, left off the last one
+                let cells: Vec<(String, String)> = columns
+                    .iter()
+                    .map(|column| match column {
+                        Column::Perms => (path.permissions.clone(), String::new()),
+                        Column::Links => (path.links.clone(), String::new()),
+                        Column::Owner => {
+                            let width = layout::width(&path.owner);
+                            let (_, after) = around(width, |widths| widths.owner, Align::Left);
+                            (path.owner.clone(), after)
+                        }
+                        Column::Size => size.clone(),
+                        Column::Time => (time_color.to_string(), String::new()),
+                        Column::Ext => {
+                            let width = layout::width(&path.extension);
+                            let (_, after) = around(width, |widths| widths.extension, Align::Left);
+                            (path.extension.clone(), after)
+                        }
+                        Column::Frag => {
+                            let width = layout::width(&path.frag);
+                            let (_, after) = around(width, |widths| widths.frag, Align::Left);
+                            (path.frag.clone(), after)
+                        }
+                        Column::Target => {
+                            let target = path.target_cell();
+                            let width = layout::width(target);
+                            let (_, after) = around(width, |widths| widths.target, Align::Left);
+                            (target.to_string(), after)
+                        }
+                        Column::Name => {
+                            let (before, after) =
+                                around(path.name_width(), |widths| widths.name, name_align);
+                            // As the last column the name can take the link
+                            // target along, unless it has a column of its own
+                            let suffix = match columns.last() == Some(&Column::Name) && !targets {
+                                true => path.link_suffix(),
+                                false => String::new(),
+                            };
+                            let name = format!(
+                                "{}{before}{}{}{suffix}",
+                                path.branch,
+                                path.name_color(&path.file_name, options),
+                                path.marks(),
+                            );
+                            (name, after)
+                        }
+                    })
+                    .collect();
+                // Nothing follows the last cell with anything in it, so that
+                // one keeps no padding
+                let shown = cells
+                    .iter()
+                    .rposition(|(text, _)| !text.is_empty())
+                    .map_or(0, |last| last + 1);
+                let mut row = path.blocks_prefix(&self.options.separator);
+                for (i, (text, padding)) in cells[..shown].iter().enumerate() {
+                    row.push_str(text);
+                    if i + 1 < shown {
+                        row.push_str(padding);
+                        row.push_str(&self.options.separator);
+                    }
+                }
+                path.print_string = row;
+            });
+        } else if self.options.tree.is_some() {
+            // One entry per line, so no padding at all
+            self.paths.iter_mut().for_each(|path| {
+                let name = path.name_color(&path.file_name, options);
+                path.print_string = format!(
+                    "{}{}{name}{}",
+                    path.blocks_prefix(" "),
+                    path.branch,
+                    path.marks()
+                );
+            });
+        } else {
+            // The grid pads the names after their markers, where it needs to
+            self.paths.iter_mut().for_each(|path| {
+                let (before, _) = around(path.name_width(), |widths| widths.name, name_align);
+                let name = path.name_color(&path.file_name, options);
+                path.print_string =
+                    format!("{}{before}{name}{}", path.blocks_prefix(" "), path.marks());
+            });
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::*;
+    use crate::path::size::{BlockSize, HumanSize};
+
+    #[test]
+    fn empty_file_and_directory_sizes_should_differ() {
+        let mut paths = Paths::default();
+        for (is_dir, bytes) in [(false, 0), (true, 0), (true, 4096)] {
+            paths.paths.push(Path {
+                is_dir,
+                bytes: Some(bytes),
+                ..Default::default()
+            });
+        }
+
+        paths.format_sizes();
+
+        let sizes: Vec<_> = paths.paths.iter().map(|path| path.size.as_str()).collect();
+        assert_eq!(sizes, vec!["0B", "-", "-"]);
+    }
+
+    #[test]
+    fn raw_bytes_should_size_directories_too() {
+        let mut paths = Paths::with_options(Options {
+            raw_bytes: true,
+            ..Default::default()
+        });
+        for (is_dir, bytes) in [(false, 0), (true, 4096)] {
+            paths.paths.push(Path {
+                is_dir,
+                bytes: Some(bytes),
+                ..Default::default()
+            });
+        }
+
+        paths.format_sizes();
+
+        let sizes: Vec<_> = paths.paths.iter().map(|path| path.size.as_str()).collect();
+        assert_eq!(sizes, vec!["0", "4096"]);
+    }
+
+    #[test]
+    fn size_string_formatter_less_than_1_kb() {
+        assert_eq!(
+            Path::size_string_formatter(495, &Options::default()),
+            "495B"
+        );
+    }
+
+    #[test]
+    fn size_string_formatter_exactly_1_kb() {
+        assert_eq!(
+            Path::size_string_formatter(1000, &Options::default()),
+            "1KB"
+        );
+    }
+
+    #[test]
+    fn size_string_formatter_less_than_1_tb() {
+        assert_eq!(
+            Path::size_string_formatter(299392942, &Options::default()),
+            "299MB"
+        );
+    }
+
+    #[test]
+    fn size_string_formatter_exactly_1_tb() {
+        assert_eq!(
+            Path::size_string_formatter(1000000000000, &Options::default()),
+            "1TB"
+        );
+    }
+
+    #[test]
+    fn size_string_formatter_more_than_1_tb() {
+        assert_eq!(
+            Path::size_string_formatter(293380504804052, &Options::default()),
+            "293TB"
+        );
+    }
+
+    #[test]
+    fn names_should_have_same_length() {
+        let path1 = Path {
+            file_name: "test".to_owned(),
+            is_dir: false,
+            size: "1kb".to_owned(),
+            time: "test".to_owned(),
+            print_string: "print".to_owned(),
+            ..Default::default()
+        };
+        let path2 = Path {
+            file_name: "test_test".to_owned(),
+            is_dir: false,
+            size: "1kb".to_owned(),
+            time: "test".to_owned(),
+            print_string: "print".to_owned(),
+            ..Default::default()
+        };
+        let mut paths = Paths::default();
+        paths.paths.push(path1);
+        paths.paths.push(path2);
+
+        let rows = long_rows(paths, vec![Column::Name, Column::Size]);
+
+        assert_eq!(rows[0].len(), rows[1].len());
+    }
+
+    #[test]
+    fn size_should_have_same_length() {
+        let path1 = Path {
+            file_name: "test".to_owned(),
+            is_dir: false,
+            size: "1kb".to_owned(),
+            time: "test".to_owned(),
+            print_string: "print".to_owned(),
+            ..Default::default()
+        };
+        let path2 = Path {
+            file_name: "test_test".to_owned(),
+            is_dir: false,
+            size: "1kb".to_owned(),
+            time: "test".to_owned(),
+            print_string: "print".to_owned(),
+            ..Default::default()
+        };
+        let mut paths = Paths::default();
+        paths.paths.push(path1);
+        paths.paths.push(path2);
+
+        paths.indentate_paths();
+
+        assert_eq!(
+            paths.paths.first().unwrap().size.len(),
+            paths.paths.get(1).unwrap().size.len(),
+        );
+    }
+
+    #[test]
+    fn date_format_should_be_corret() {
+        // Mon Jan 30 2023 20:37:54 UTC+0
+        let time = SystemTime::UNIX_EPOCH + Duration::from_millis(1675111074521);
+        let time_formatted = Path::set_time(time, TimeStyle::Default, Precision::Min, true);
+
+        assert_eq!(time_formatted, "30 Jan 20:37")
+    }
+
+    #[test]
+    fn with_options_should_setup() {
+        let all = true;
+        let long = true;
+        let tree = Some("dir".to_owned());
+
+        let paths = Paths::with_options(Options {
+            all,
+            long,
+            tree,
+            ..Default::default()
+        });
+
+        assert_eq!(paths.options.all, all);
+        assert_eq!(paths.options.long, long);
+        assert_eq!(paths.options.tree, Some("dir".to_owned()));
+    }
+
+    #[test]
+    fn no_all_argument_should_not_print_dot_files() {
+        let all = false;
+        let long = false;
+        let tree = None;
+        let mut paths = Paths::with_options(Options {
+            all,
+            long,
+            tree,
+            ..Default::default()
+        });
+
+        let mut path1 = Path {
+            file_name: ".test".to_owned(),
+            is_dir: false,
+            size: "1kb".to_owned(),
+            time: "test".to_owned(),
+            print_string: "print".to_owned(),
+            ..Default::default()
+        };
+
+        colored::control::set_override(true);
+        paths.paths.push(path1.clone());
+        paths.filter_paths();
+        paths.print_constructor();
+        // This is need as this is the result of any string using the Colored white fn
+        path1.print_string = "\u{1b}[37m.test\u{1b}[0m".to_owned();
+
+        assert!(!paths.paths.contains(&path1));
+    }
+
+    #[test]
+    fn all_argument_should_print_dot_files() {
+        let all = true;
+        let long = false;
+        let tree = None;
+        let mut paths = Paths::with_options(Options {
+            all,
+            long,
+            tree,
+            ..Default::default()
+        });
+
+        let mut path1 = Path {
+            file_name: ".test".to_owned(),
+            is_dir: false,
+            size: "1kb".to_owned(),
+            time: "test".to_owned(),
+            print_string: "print".to_owned(),
+            ..Default::default()
+        };
+
+        colored::control::set_override(true);
+        paths.paths.push(path1.clone());
+        paths.filter_paths();
+        paths.print_constructor();
+        // This is need as this is the result of any string using the Colored white fn
+        path1.print_string = "\u{1b}[37m.test\u{1b}[0m".to_owned();
+
+        assert!(paths.paths.contains(&path1));
+    }
+
+    #[test]
+    fn long_argument_should_print_extra_columns() {
+        let mut paths = Paths::with_options(Options {
+            long: true,
+            ..Default::default()
+        });
+        paths.paths.push(Path {
+            file_name: "test".to_owned(),
+            permissions: "-rw-r--r--".to_owned(),
+            links: "1".to_owned(),
+            owner: "user".to_owned(),
+            size: "1KB".to_owned(),
+            time: "30 Jan 20:37".to_owned(),
+            ..Default::default()
+        });
+
+        paths.print_constructor();
+
+        assert!(paths.paths[0]
+            .print_string
+            .starts_with("-rw-r--r-- 1 user "));
+        assert!(paths.paths[0].print_string.contains("30 Jan 20:37"));
+    }
+
+    #[test]
+    fn empty_paths_should_have_no_biggest_len() {
+        let mut paths = Paths::default();
+
+        assert_eq!(paths.get_biggest_str_len(), (0, 0));
+        paths.indentate_paths();
+        assert!(paths.paths.is_empty());
+    }
+
+    #[test]
+    fn only_dot_files_should_leave_nothing_to_print() {
+        let mut paths = Paths::default();
+        paths.paths.push(Path {
+            file_name: ".hidden".to_owned(),
+            ..Default::default()
+        });
+
+        paths.filter_paths();
+        paths.indentate_paths();
+        paths.print_constructor();
+
+        assert!(paths.paths.is_empty());
+    }
+
+    #[test]
+    fn size_string_formatter_fixed_k() {
+        let options = Options {
+            block_size: BlockSize::parse("K").ok(),
+            ..Default::default()
+        };
+
+        assert_eq!(Path::size_string_formatter(495, &options), "1K");
+        assert_eq!(Path::size_string_formatter(299392942, &options), "292376K");
+    }
+
+    #[test]
+    fn raw_bytes_should_override_every_unit() {
+        let options = Options {
+            raw_bytes: true,
+            block_size: BlockSize::parse("M").ok(),
+            human: Some(HumanSize::Binary),
+            ..Default::default()
+        };
+
+        assert_eq!(Path::size_string_formatter(0, &options), "0");
+        assert_eq!(
+            Path::size_string_formatter(299392942, &options),
+            "299392942"
+        );
+    }
+
+    #[test]
+    fn size_string_formatter_fixed_m() {
+        let options = Options {
+            block_size: BlockSize::parse("M").ok(),
+            ..Default::default()
+        };
+
+        assert_eq!(Path::size_string_formatter(495, &options), "1M");
+        assert_eq!(Path::size_string_formatter(299392942, &options), "286M");
+    }
+
+    #[test]
+    fn size_both_should_align_on_the_byte_count() {
+        let mut paths = Paths::with_options(Options {
+            long: true,
+            size_both: true,
+            ..Default::default()
+        });
+        for bytes in [512, 1_048_576, 5000] {
+            paths.paths.push(Path {
+                file_name: "a".to_owned(),
+                bytes: Some(bytes),
+                ..Default::default()
+            });
+        }
+
+        paths.format_sizes();
+        let rows = long_rows(paths, vec![Column::Size, Column::Name]);
+
+        assert_eq!(
+            rows,
+            [
+                "    512         a",
+                "1048576 (1.0M)  a",
+                "   5000 (4.9K)  a"
+            ]
+        );
+    }
+
+    #[test]
+    fn fixed_block_size_should_use_one_suffix_for_all_rows() {
+        let mut paths = Paths::with_options(Options {
+            block_size: BlockSize::parse("K").ok(),
+            ..Default::default()
+        });
+        for bytes in [12, 4096, 10_000_000] {
+            paths.paths.push(Path {
+                bytes: Some(bytes),
+                ..Default::default()
+            });
+        }
+
+        paths.format_sizes();
+
+        assert!(paths.paths.iter().all(|path| path.size.ends_with('K')));
+    }
+
+    #[test]
+    fn dotfiles_only_should_print_only_dot_files() {
+        let mut paths = Paths::with_options(Options {
+            dotfiles_only: true,
+            ..Default::default()
+        });
+        for file_name in [".bashrc", "notes.txt", "..", ".config"] {
+            paths.paths.push(Path {
+                file_name: file_name.to_owned(),
+                ..Default::default()
+            });
+        }
+
+        paths.filter_paths();
+
+        let names: Vec<&str> = paths.paths.iter().map(|p| p.file_name.as_str()).collect();
+        assert_eq!(names, vec![".bashrc", ".config"]);
+    }
+
+    #[test]
+    fn modified_since_should_keep_only_newer_entries() {
+        let now = SystemTime::UNIX_EPOCH + Duration::from_secs(1_700_000_000);
+        let mut paths = Paths::with_options(Options {
+            modified_since: Some(now - Duration::from_secs(15 * 60)),
+            ..Default::default()
+        });
+        for (file_name, age) in [
+            ("fresh", Some(60)),
+            ("stale", Some(60 * 60)),
+            ("unknown", None),
+        ] {
+            paths.paths.push(Path {
+                file_name: file_name.to_owned(),
+                modified: age.map(|age| now - Duration::from_secs(age)),
+                ..Default::default()
+            });
+        }
+
+        paths.filter_paths();
+
+        let names: Vec<&str> = paths.paths.iter().map(|p| p.file_name.as_str()).collect();
+        assert_eq!(names, vec!["fresh"]);
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn xattrs_should_follow_their_entry() {
+        let dir = std::env::temp_dir().join("lsr_xattrs");
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join("plain"), "").unwrap();
+        fs::write(dir.join("tagged"), "").unwrap();
+        // Not every filesystem holds user attributes
+        if xattr::set(dir.join("tagged"), "user.comment", b"hello").is_err() {
+            fs::remove_dir_all(&dir).unwrap();
+            return;
+        }
+        xattr::set(dir.join("tagged"), "user.x", b"").unwrap();
+        let options = Options {
+            long: true,
+            xattrs: true,
+            columns: vec![Column::Name],
+            ..Default::default()
+        };
+
+        let out = crate::list_directory(&dir, &options).unwrap().render();
+        fs::remove_dir_all(&dir).unwrap();
+
+        let lines: Vec<&str> = out.lines().collect();
+        assert_eq!(lines.len(), 4, "{out}");
+        assert!(lines[1].contains("tagged"), "{out}");
+        let mut attributes = lines[2..].to_vec();
+        attributes.sort();
+        assert_eq!(attributes, ["    user.comment  5", "    user.x        0"]);
+    }
+
+    #[test]
+    fn time_kind_should_drive_both_sort_and_display() {
+        let at = |secs| Some(SystemTime::UNIX_EPOCH + Duration::from_secs(secs));
+        let listing = |time_kind| {
+            let mut paths = Paths::with_options(Options {
+                sort: SortKey::Time,
+                time_kind,
+                time_style: TimeStyle::Iso8601,
+                utc: true,
+                ..Default::default()
+            });
+            // Written long ago but read just now, and the other way around
+            for (file_name, modified, accessed) in [("old", 0, 2_000), ("new", 1_000, 500)] {
+                paths.paths.push(Path {
+                    file_name: file_name.to_owned(),
+                    modified: at(modified),
+                    accessed: at(accessed),
+                    ..Default::default()
+                });
+            }
+            paths.sort_paths();
+            paths.format_times();
+            paths
+                .paths
+                .iter()
+                .map(|path| format!("{} {}", path.file_name, path.time))
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(
+            listing(TimeKind::Modification),
+            [
+                "new 1970-01-01T00:16:40+00:00",
+                "old 1970-01-01T00:00:00+00:00"
+            ]
+        );
+        assert_eq!(
+            listing(TimeKind::Access),
+            [
+                "old 1970-01-01T00:33:20+00:00",
+                "new 1970-01-01T00:08:20+00:00"
+            ]
+        );
+    }
+
+    #[test]
+    fn hidden_attribute_should_count_as_hidden() {
+        let mut paths = Paths::default();
+        paths.paths.push(Path {
+            file_name: "desktop.ini".to_owned(),
+            hidden_attribute: true,
+            ..Default::default()
+        });
+
+        paths.filter_paths();
+
+        assert!(paths.paths.is_empty());
+    }
+
+    #[test]
+    fn heatmap_should_color_time_by_age() {
+        let mut paths = Paths::with_options(Options {
+            long: true,
+            heatmap: true,
+            ..Default::default()
+        });
+        paths.paths.push(Path {
+            file_name: "old".to_owned(),
+            time: "1 Jan 00:00".to_owned(),
+            modified: Some(SystemTime::UNIX_EPOCH),
+            ..Default::default()
+        });
+
+        colored::control::set_override(true);
+        paths.print_constructor();
+
+        let expected = "1 Jan 00:00".red().to_string();
+        assert!(paths.paths[0].print_string.contains(&expected));
+    }
+
+    #[test]
+    fn color_by_should_pick_one_strategy_for_names() {
+        let entries = [("old.rs", false), ("new.rs", false), ("dir", true)];
+        let names = |color_by| {
+            let mut paths = Paths::with_options(Options {
+                color_by,
+                ..Default::default()
+            });
+            for (i, (name, is_dir)) in entries.into_iter().enumerate() {
+                paths.paths.push(Path {
+                    file_name: name.to_owned(),
+                    full_path: PathBuf::from(name),
+                    is_dir,
+                    modified: Some(if i == 0 {
+                        SystemTime::UNIX_EPOCH
+                    } else {
+                        SystemTime::now()
+                    }),
+                    ..Default::default()
+                });
+            }
+            colored::control::set_override(true);
+            paths.print_constructor();
+            paths
+                .paths
+                .into_iter()
+                .map(|path| path.print_string)
+                .collect::<Vec<_>>()
+        };
+
+        let theme = Theme::default();
+        assert_eq!(
+            names(ColorBy::Type),
+            [
+                theme.file("old.rs").to_string(),
+                theme.file("new.rs").to_string(),
+                theme.directory("dir").to_string()
+            ]
+        );
+        assert_eq!(
+            names(ColorBy::Extension),
+            [
+                theme.extension("old.rs", "rs").to_string(),
+                theme.extension("new.rs", "rs").to_string(),
+                theme.directory("dir").to_string()
+            ]
+        );
+        assert_eq!(
+            names(ColorBy::Age),
+            [
+                "old.rs".red().to_string(),
+                "new.rs".bright_green().to_string(),
+                "dir".bright_green().to_string()
+            ]
+        );
+        assert_eq!(names(ColorBy::None), ["old.rs", "new.rs", "dir"]);
+    }
+
+    #[test]
+    fn recent_should_set_apart_fresh_times() {
+        let now = SystemTime::now();
+        let mut paths = Paths::with_options(Options {
+            long: true,
+            recent_since: now.checked_sub(Duration::from_secs(3600)),
+            ..Default::default()
+        });
+        for (file_name, modified) in [("fresh", now), ("stale", SystemTime::UNIX_EPOCH)] {
+            paths.paths.push(Path {
+                file_name: file_name.to_owned(),
+                time: file_name.to_owned(),
+                modified: Some(modified),
+                ..Default::default()
+            });
+        }
+
+        colored::control::set_override(true);
+        paths.print_constructor();
+
+        let recent = Theme::Dark.recent("fresh").to_string();
+        let normal = Theme::Dark.time("stale").to_string();
+        assert!(paths.paths[0].print_string.contains(&recent));
+        assert!(paths.paths[1].print_string.contains(&normal));
+    }
+
+    fn sorted_names(sort: SortKey, entries: &[(&str, u64)]) -> Vec<String> {
+        let mut paths = Paths::with_options(Options {
+            sort,
+            ..Default::default()
+        });
+        for (file_name, bytes) in entries {
+            paths.paths.push(Path {
+                file_name: file_name.to_string(),
+                bytes: Some(*bytes),
+                ..Default::default()
+            });
+        }
+        paths.sort_paths();
+        paths.paths.into_iter().map(|path| path.file_name).collect()
+    }
+
+    #[test]
+    fn sort_by_name_should_be_alphabetical() {
+        let names = sorted_names(SortKey::Name, &[("b", 0), ("c", 0), ("a", 0)]);
+
+        assert_eq!(names, vec!["a", "b", "c"]);
+    }
+
+    #[test]
+    fn sort_by_size_should_put_largest_first() {
+        let names = sorted_names(SortKey::Size, &[("b", 10), ("c", 30), ("a", 10)]);
+
+        assert_eq!(names, vec!["c", "a", "b"]);
+    }
+
+    #[test]
+    fn sort_by_length_should_count_characters() {
+        let entries = [("ccc", 0), ("éé", 0), ("bb", 0), ("a", 0), ("dddd", 0)];
+
+        let names = sorted_names(SortKey::Length, &entries);
+
+        assert_eq!(names, vec!["a", "bb", "éé", "ccc", "dddd"]);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn actual_size_should_show_allocated_blocks_of_sparse_files() {
+        let dir = std::env::temp_dir().join("lsr_sparse");
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        let sparse = fs::File::create(dir.join("sparse")).unwrap();
+        sparse.set_len(100 * MEGABYTE).unwrap();
+
+        let mut sizes = Vec::new();
+        for actual_size in [false, true] {
+            let options = Options {
+                long: true,
+                actual_size,
+                ..Default::default()
+            };
+            let mut paths = crate::list_directory(&dir, &options).unwrap();
+            paths.format_sizes();
+            sizes.push(paths.paths[0].size.clone());
+        }
+        fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(sizes[0], "100MB");
+        // Nothing was written, so at most a block or two is allocated
+        assert_ne!(sizes[1], "100MB");
+    }
+
+    #[test]
+    fn frag_should_flag_sparse_files_and_slack() {
+        let mut paths = Paths::default();
+        for (bytes, allocated, is_dir, is_symlink) in [
+            (Some(100 * MEGABYTE), Some(4096), false, false),
+            (Some(100), Some(4096), false, false),
+            (Some(6000), Some(8192), false, false),
+            (Some(0), Some(0), false, false),
+            (Some(4096), Some(4096), true, false),
+            // A link's own size is its target's path, not data on disk
+            (Some(12), Some(0), false, true),
+            (Some(10), None, false, false),
+        ] {
+            paths.paths.push(Path {
+                bytes,
+                allocated,
+                is_dir,
+                is_symlink,
+                ..Default::default()
+            });
+        }
+
+        paths.compare_allocation();
+
+        let frag: Vec<_> = paths.paths.iter().map(|path| path.frag.as_str()).collect();
+        assert_eq!(
+            frag,
+            ["0.00x sparse", "40.96x slack", "1.37x", "-", "-", "-", "-"]
+        );
+    }
+
+    #[test]
+    fn size_blocks_should_round_up_and_total() {
+        let count = |block_size| {
+            let mut paths = Paths::with_options(Options {
+                size_blocks: true,
+                block_size,
+                ..Default::default()
+            });
+            for allocated in [Some(0), Some(4096), Some(8192 + 512), None] {
+                paths.paths.push(Path {
+                    allocated,
+                    ..Default::default()
+                });
+            }
+            let total = paths.count_blocks();
+            assert_eq!(paths.blocks_total(), total);
+            let blocks: Vec<_> = paths.paths.iter().map(|path| path.blocks.clone()).collect();
+            (blocks, total)
+        };
+
+        let (blocks, total) = count(None);
+        assert_eq!(blocks, ["0", "4", "9", "?"]);
+        assert_eq!(total, "total 13");
+        let (blocks, total) = count(BlockSize::parse("512").ok());
+        assert_eq!(blocks, ["0", "8", "17", "?"]);
+        assert_eq!(total, "total 25");
+    }
+
+    #[test]
+    fn max_name_bytes_should_keep_an_outlier_from_widening_the_columns() {
+        let mut paths = Paths::default();
+        for file_name in ["a".to_string(), "x".repeat(5000)] {
+            paths.paths.push(Path {
+                file_name,
+                permissions: "-".to_owned(),
+                ..Default::default()
+            });
+        }
+
+        paths.cap_names(8);
+        let rows = long_rows(paths, vec![Column::Name, Column::Perms]);
+
+        assert_eq!(rows, ["a      -", "xxxxx… -"]);
+    }
+
+    #[test]
+    fn reverse_should_flip_the_sort() {
+        let mut paths = Paths::with_options(Options {
+            sort: SortKey::Size,
+            reverse: true,
+            ..Default::default()
+        });
+        for (file_name, bytes) in [("b", 10), ("c", 30), ("a", 10)] {
+            paths.paths.push(Path {
+                file_name: file_name.to_string(),
+                bytes: Some(bytes),
+                ..Default::default()
+            });
+        }
+
+        paths.sort_paths();
+
+        let names: Vec<_> = paths.paths.iter().map(|p| p.file_name.as_str()).collect();
+        assert_eq!(names, vec!["b", "a", "c"]);
+    }
+
+    #[test]
+    fn sort_none_should_keep_directory_order() {
+        let names = sorted_names(SortKey::None, &[("b", 0), ("c", 0), ("a", 0)]);
+
+        assert_eq!(names, vec!["b", "c", "a"]);
+    }
+
+    #[test]
+    fn columns_should_pick_and_order_the_long_fields() {
+        let mut paths = Paths::with_options(Options {
+            long: true,
+            columns: vec![Column::Size, Column::Name, Column::Perms],
+            ..Default::default()
+        });
+        for (file_name, bytes) in [("a", 12), ("longer", 3000)] {
+            paths.paths.push(Path {
+                file_name: file_name.to_owned(),
+                bytes: Some(bytes),
+                permissions: "-rw-r--r--".to_owned(),
+                ..Default::default()
+            });
+        }
+
+        colored::control::set_override(true);
+        paths.format_sizes();
+        paths.indentate_paths();
+        paths.print_constructor();
+
+        let line = format!(
+            "{}  {}      -rw-r--r--",
+            size::colorize("12B", 12),
+            "a".white()
+        );
+        assert_eq!(paths.paths[0].print_string, line);
+    }
+
+    #[test]
+    fn size_should_be_right_aligned_by_default() {
+        let mut paths = Paths::default();
+        for size in ["1KB", "100MB"] {
+            paths.paths.push(Path {
+                file_name: "x".to_owned(),
+                size: size.to_owned(),
+                ..Default::default()
+            });
+        }
+
+        let rows = long_rows(paths, vec![Column::Size, Column::Name]);
+
+        assert_eq!(rows, ["  1KB  x", "100MB  x"]);
+    }
+
+    #[test]
+    fn size_can_be_left_aligned() {
+        let mut paths = Paths::with_options(Options {
+            size_align: Align::Left,
+            ..Default::default()
+        });
+        for size in ["1KB", "100MB"] {
+            paths.paths.push(Path {
+                file_name: "x".to_owned(),
+                size: size.to_owned(),
+                ..Default::default()
+            });
+        }
+
+        let rows = long_rows(paths, vec![Column::Size, Column::Name]);
+
+        assert_eq!(rows[0], "1KB    x");
+    }
+
+    #[test]
+    fn name_should_be_left_aligned_by_default() {
+        let mut paths = Paths::default();
+        for file_name in ["a", "abc"] {
+            paths.paths.push(Path {
+                file_name: file_name.to_owned(),
+                permissions: "-".to_owned(),
+                ..Default::default()
+            });
+        }
+
+        let rows = long_rows(paths, vec![Column::Name, Column::Perms]);
+
+        assert_eq!(rows[0], "a   -");
+    }
+
+    #[test]
+    fn owners_should_align_by_display_width() {
+        let mut paths = Paths::default();
+        for owner in ["jürgen staff", "bob staff"] {
+            paths.paths.push(Path {
+                file_name: "x".to_owned(),
+                owner: owner.to_owned(),
+                ..Default::default()
+            });
+        }
+
+        let rows = long_rows(paths, vec![Column::Owner, Column::Name]);
+
+        assert_eq!(rows, ["jürgen staff x", "bob staff    x"]);
+    }
+
+    #[test]
+    fn summary_should_count_symlinks_separately() {
+        let mut paths = Paths::default();
+        for (is_dir, is_symlink) in [(true, false), (false, false), (false, false), (false, true)] {
+            paths.paths.push(Path {
+                is_dir,
+                is_symlink,
+                ..Default::default()
+            });
+        }
+
+        assert_eq!(paths.summary(), "1 directory, 2 files, 1 symlink");
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn broken_symlink_should_be_counted_as_symlink() {
+        let dir = std::env::temp_dir().join("lsr_broken_symlink_count");
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir(&dir).unwrap();
+        std::os::unix::fs::symlink(dir.join("missing"), dir.join("dangling")).unwrap();
+
+        let mut paths = Paths::default();
+        for entry in fs::read_dir(&dir).unwrap() {
+            paths.paths.push(Path::new(entry.unwrap()).unwrap());
+        }
+        fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(paths.summary(), "0 directories, 0 files, 1 symlink");
+    }
+
+    #[test]
+    fn yaml_output_should_bypass_colors() {
+        let mut paths = Paths::with_options(Options {
+            output: Output::Yaml,
+            ..Default::default()
+        });
+        paths.paths.push(Path {
+            file_name: "src".to_owned(),
+            is_dir: true,
+            bytes: Some(4096),
+            ..Default::default()
+        });
+
+        colored::control::set_override(true);
+        let mut out = Vec::new();
+        paths.write_to(&mut out).unwrap();
+
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "- name: src\n  type: directory\n  size: 4096\n  modified: null\n"
+        );
+    }
+
+    #[test]
+    fn unreadable_entry_should_show_placeholders() {
+        let mut paths = Paths::default();
+        paths
+            .paths
+            .push(Path::unreadable(PathBuf::from("gone"), None));
+
+        paths.format_sizes();
+
+        let path = &paths.paths[0];
+        assert_eq!(path.size, "?");
+        assert_eq!(path.time, "?");
+        assert!(path.record().modified.is_none());
+    }
+
+    #[cfg(unix)]
+    fn indicators(indicators: Indicators) -> Vec<String> {
+        indicators_with(indicators, false)
+    }
+
+    #[cfg(unix)]
+    fn indicators_with(indicators: Indicators, classify_targets: bool) -> Vec<String> {
+        use std::os::unix::fs::PermissionsExt;
+
+        let dir =
+            std::env::temp_dir().join(format!("lsr_indicators_{indicators:?}_{classify_targets}"));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(dir.join("sub")).unwrap();
+        fs::write(dir.join("run"), "").unwrap();
+        fs::set_permissions(dir.join("run"), fs::Permissions::from_mode(0o755)).unwrap();
+        fs::write(dir.join("plain"), "").unwrap();
+        std::os::unix::fs::symlink("run", dir.join("link")).unwrap();
+        if classify_targets {
+            std::os::unix::fs::symlink("sub", dir.join("link_dir")).unwrap();
+            std::os::unix::fs::symlink("plain", dir.join("link_plain")).unwrap();
+            std::os::unix::fs::symlink("gone", dir.join("link_broken")).unwrap();
+        }
+
+        let mut paths = crate::list_directory(
+            &dir,
+            &Options {
+                indicators,
+                classify_targets,
+                ..Default::default()
+            },
+        )
+        .unwrap();
+        fs::remove_dir_all(&dir).unwrap();
+        paths.sort_paths();
+        paths.classify();
+        paths.indentate_paths();
+
+        paths
+            .paths
+            .iter()
+            .map(|path| format!("{}{}", path.file_name.trim_end(), path.indicator))
+            .collect()
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn exec_suffix_should_mark_only_executables() {
+        assert_eq!(
+            indicators(Indicators::Executables),
+            vec!["link", "plain", "run*", "sub"]
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn classify_should_mark_every_type() {
+        assert_eq!(
+            indicators(Indicators::Classify),
+            vec!["link@", "plain", "run*", "sub/"]
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn classify_targets_should_mark_links_by_what_they_lead_to() {
+        assert_eq!(
+            indicators_with(Indicators::Classify, true),
+            vec![
+                "link*",
+                "link_broken@",
+                "link_dir/",
+                "link_plain@",
+                "plain",
+                "run*",
+                "sub/"
+            ]
+        );
+    }
+
+    #[test]
+    fn indicator_should_follow_the_name_uncolored() {
+        let mut paths = Paths::with_options(Options {
+            indicators: Indicators::Classify,
+            ..Default::default()
+        });
+        for (name, is_dir) in [("src", true), ("a_long_name", false)] {
+            paths.paths.push(Path {
+                file_name: name.to_owned(),
+                is_dir,
+                ..Default::default()
+            });
+        }
+
+        colored::control::set_override(true);
+        paths.classify();
+        paths.indentate_paths();
+        paths.print_constructor();
+
+        assert_eq!(paths.paths[0].print_string, format!("{}/", "src".blue()));
+        assert_eq!(
+            paths.grid_lines(Some(30)),
+            vec![format!("{}/  {}", "src".blue(), "a_long_name".white())]
+        );
+    }
+
+    /// The name with what follows it, as a listing shows it.
+    /// The long rows `columns` make of `paths` once aligned, uncolored.
+    fn long_rows(mut paths: Paths, columns: Vec<Column>) -> Vec<String> {
+        paths.options.long = true;
+        paths.options.columns = columns;
+        paths.indentate_paths();
+        paths.print_constructor();
+        paths
+            .paths
+            .iter()
+            .map(|path| layout::plain(&path.print_string))
+            .collect()
+    }
+
+    fn shown(path: &Path) -> String {
+        format!("{}{}", path.file_name, path.marks())
+    }
+
+    #[test]
+    fn child_count_should_respect_all() {
+        let dir = std::env::temp_dir().join("lsr_child_count");
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(dir.join("sub")).unwrap();
+        fs::write(dir.join("sub").join("visible"), "").unwrap();
+        fs::write(dir.join("sub").join(".hidden"), "").unwrap();
+
+        let mut counts = Vec::new();
+        for all in [false, true] {
+            let mut paths = Paths::with_options(Options {
+                all,
+                ..Default::default()
+            });
+            paths
+                .paths
+                .push(Path::from_file_type(dir.join("sub"), None));
+            paths.paths[0].is_dir = true;
+            paths.count_children();
+            counts.push(shown(&paths.paths[0]));
+        }
+        fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(counts, vec!["sub [1]", "sub [2]"]);
+    }
+
+    #[test]
+    fn child_count_should_mark_unreadable_dirs() {
+        let mut paths = Paths::default();
+        paths.paths.push(Path {
+            file_name: "gone".to_owned(),
+            full_path: PathBuf::from("does-not-exist"),
+            is_dir: true,
+            ..Default::default()
+        });
+
+        paths.count_children();
+
+        assert_eq!(shown(&paths.paths[0]), "gone [?]");
+    }
+
+    #[test]
+    fn mark_empty_should_only_count_what_would_be_shown() {
+        let dir = std::env::temp_dir().join("lsr_mark_empty");
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(dir.join("dotfiles")).unwrap();
+        fs::create_dir_all(dir.join("empty")).unwrap();
+        fs::create_dir_all(dir.join("full")).unwrap();
+        fs::write(dir.join("dotfiles").join(".hidden"), "").unwrap();
+        fs::write(dir.join("full").join("visible"), "").unwrap();
+
+        let mut marked = Vec::new();
+        for all in [false, true] {
+            let mut paths = Paths::with_options(Options {
+                all,
+                ..Default::default()
+            });
+            for name in ["dotfiles", "empty", "full", "missing"] {
+                paths.paths.push(Path {
+                    file_name: name.to_owned(),
+                    full_path: dir.join(name),
+                    is_dir: true,
+                    ..Default::default()
+                });
+            }
+            paths.mark_empty();
+            marked.push(paths.paths.iter().map(shown).collect::<Vec<_>>());
+        }
+        fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(
+            marked[0],
+            ["dotfiles (empty)", "empty (empty)", "full", "missing (?)"]
+        );
+        assert_eq!(
+            marked[1],
+            ["dotfiles", "empty (empty)", "full", "missing (?)"]
+        );
+    }
+
+    #[test]
+    fn render_should_match_print_and_be_repeatable() {
+        let mut paths = Paths::with_options(Options {
+            width: Some(80),
+            count: true,
+            ..Default::default()
+        });
+        for file_name in ["b", "a"] {
+            paths.paths.push(Path {
+                file_name: file_name.to_owned(),
+                ..Default::default()
+            });
+        }
+
+        let rendered = paths.render();
+        assert_eq!(paths.render(), rendered);
+
+        let mut out = Vec::new();
+        paths.write_to(&mut out).unwrap();
+        assert_eq!(String::from_utf8(out).unwrap(), rendered);
+    }
+
+    #[test]
+    fn size_string_formatter_short_style() {
+        let options = Options {
+            size_style: SizeStyle::Short,
+            ..Default::default()
+        };
+
+        assert_eq!(Path::size_string_formatter(495, &options), "495B");
+        assert_eq!(Path::size_string_formatter(1000, &options), "1K");
+        assert_eq!(Path::size_string_formatter(299392942, &options), "299M");
+        assert_eq!(
+            Path::size_string_formatter(293380504804052, &options),
+            "293T"
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn broken_symlink_should_be_red_with_target() {
+        let dir = std::env::temp_dir().join("lsr_broken_symlink_red");
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir(&dir).unwrap();
+        std::os::unix::fs::symlink("missing", dir.join("dangling")).unwrap();
+
+        let mut paths = Paths::with_options(Options {
+            long: true,
+            ..Default::default()
+        });
+        for entry in fs::read_dir(&dir).unwrap() {
+            let entry = entry.unwrap();
+            let light = Path::from_file_type(entry.path(), entry.file_type().ok());
+            assert!(light.broken);
+            paths.paths.push(Path::new(entry).unwrap());
+        }
+        fs::remove_dir_all(&dir).unwrap();
+
+        colored::control::set_override(true);
+        paths.print_constructor();
+
+        let line = &paths.paths[0].print_string;
+        assert!(line.contains(&"dangling".red().to_string()));
+        assert!(line.ends_with(" -> missing (broken)"));
+    }
+
+    fn summary_paths(options: Options) -> Paths {
+        let mut paths = Paths::with_options(options);
+        for (file_name, bytes) in [("a", 1000), (".hidden", 3000)] {
+            paths.paths.push(Path {
+                file_name: file_name.to_owned(),
+                bytes: Some(bytes),
+                ..Default::default()
+            });
+        }
+        paths
+    }
+
+    #[test]
+    fn highlights_should_name_the_largest_and_oldest_entries() {
+        let day = Duration::from_secs(24 * 60 * 60);
+        let mut paths = Paths::with_options(Options {
+            utc: true,
+            ..Default::default()
+        });
+        for (file_name, is_dir, bytes, days) in [
+            ("sub", true, 9000, 3),
+            ("b.bin", false, 2000, 2),
+            ("a.bin", false, 2000, 1),
+            ("old.txt", false, 10, 0),
+        ] {
+            paths.paths.push(Path {
+                file_name: file_name.to_owned(),
+                is_dir,
+                bytes: Some(bytes),
+                modified: Some(SystemTime::UNIX_EPOCH + days * day),
+                ..Default::default()
+            });
+        }
+
+        assert_eq!(
+            paths.highlights(),
+            vec!["largest: a.bin (2KB)", "oldest: old.txt (1970-01-01)"]
+        );
+    }
+
+    #[test]
+    fn total_should_reflect_what_is_printed() {
+        assert_eq!(summary_paths(Options::default()).total(), "total size: 1KB");
+
+        let all = summary_paths(Options {
+            all: true,
+            ..Default::default()
+        });
+        assert_eq!(all.total(), "total size: 4KB");
+    }
+
+    #[test]
+    fn total_should_leave_out_unmeasured_directories() {
+        let mut paths = summary_paths(Options::default());
+        paths.paths.push(Path {
+            file_name: "sub".to_owned(),
+            is_dir: true,
+            bytes: Some(8000),
+            ..Default::default()
+        });
+        assert_eq!(paths.total(), "total size: 1KB");
+
+        paths.options.dir_sizes = true;
+        assert_eq!(paths.total(), "total size: 9KB");
+    }
+
+    #[test]
+    fn count_all_should_include_hidden_files_without_all() {
+        let paths = summary_paths(Options {
+            count_all: true,
+            ..Default::default()
+        });
+
+        assert_eq!(paths.total(), "total size: 4KB");
+        assert_eq!(paths.summary(), "0 directories, 2 files, 0 symlinks");
+    }
+
+    #[test]
+    fn bars_should_keep_columns_aligned() {
+        let mut paths = Paths::with_options(Options {
+            long: true,
+            bars: true,
+            ..Default::default()
+        });
+        for (is_dir, bytes) in [(false, 100), (true, 4096), (false, 50)] {
+            paths.paths.push(Path {
+                is_dir,
+                bytes: Some(bytes),
+                ..Default::default()
+            });
+        }
+
+        colored::control::set_override(true);
+        paths.format_sizes();
+        paths.draw_bars();
+        paths.indentate_paths();
+
+        assert_eq!(paths.paths[0].bar, "▇▇▇▇▇▇▇▇");
+        assert_eq!(paths.paths[1].bar, "        ");
+        assert_eq!(paths.paths[2].bar, "▇▇▇▇▁▁▁▁");
+    }
+
+    #[cfg(windows)]
+    #[test]
+    fn reserved_windows_names_should_join_as_they_are() {
+        let mut paths = Paths::default();
+        for name in ["CON", "name. "] {
+            let full_path = PathBuf::from(r"C:\listing").join(name);
+            paths.paths.push(Path {
+                source: Some("src".to_string()),
+                ..Path::from_file_type(full_path, None)
+            });
+        }
+
+        paths.prefix_sources();
+
+        let names: Vec<_> = paths.paths.iter().map(Path::name).collect();
+        assert_eq!(names, vec![r"src\CON", r"src\name. "]);
     }
 }
@@ -0,0 +1,189 @@
+use serde::Serialize;
+
+/// Plain, uncolored view of an entry for the structured output formats.
+#[derive(Debug, Serialize, PartialEq)]
+pub struct Record {
+    pub name: String,
+    /// The directory argument the entry came from, in a merged listing.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub source: Option<String>,
+    #[serde(rename = "type")]
+    pub kind: &'static str,
+    /// Size in bytes, if it could be read.
+    pub size: Option<u64>,
+    /// Modification time as RFC 3339, if it could be read.
+    pub modified: Option<String>,
+}
+
+pub fn to_yaml(records: &[Record]) -> String {
+    // Only maps with non-string keys can fail, and a record has none
+    serde_yaml::to_string(records).expect("records always serialize to YAML")
+}
+
+/// TOML has no top-level arrays, so the records go under a `files` key.
+#[derive(Serialize)]
+struct Document<'a> {
+    files: &'a [Record],
+}
+
+/// An array of tables, `[[files]]`. TOML has no null either, so a size or
+/// time that couldn't be read is left out of its table.
+pub fn to_toml(records: &[Record]) -> String {
+    // Every field is a plain string or number, which TOML can always hold
+    toml::to_string(&Document { files: records }).expect("records always serialize to TOML")
+}
+
+/// The record as one line of JSON, for `--jsonl`, with the same fields as
+/// the YAML and `null` for what couldn't be read.
+pub fn to_json_line(record: &Record) -> String {
+    // Plain strings and numbers, which JSON can always hold
+    serde_json::to_string(record).expect("records always serialize to JSON") + "\n"
+}
+
+/// An entry of a tree with what is below it, for `--tree --json`.
+#[derive(Debug, Serialize, PartialEq)]
+pub struct Node {
+    #[serde(flatten)]
+    pub record: Record,
+    /// The entries of a directory the tree went into. Files have none, and
+    /// neither do directories cut off by the depth limit or that couldn't
+    /// be read, which sets them apart from empty ones.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub children: Option<Vec<Node>>,
+}
+
+/// The whole tree as one line of nested JSON objects, each with the fields
+/// of a JSON line and a `children` array where it has children.
+pub fn to_json_tree(node: &Node) -> String {
+    serde_json::to_string(node).expect("records always serialize to JSON") + "\n"
+}
+
+/// The names on one line for `--shell`, each quoted as a POSIX shell word
+/// so the line can be pasted into a command as it is. Nothing is written
+/// for no names.
+pub fn to_shell<'a>(names: impl IntoIterator<Item = &'a str>) -> String {
+    let words: Vec<String> = names.into_iter().map(shell_word).collect();
+    if words.is_empty() {
+        String::new()
+    } else {
+        format!("{}\n", words.join(" "))
+    }
+}
+
+/// Single quotes keep every character as it is, newlines included, so only
+/// a quote itself needs care: it closes the quoting, is escaped, and opens
+/// it again.
+fn shell_word(text: &str) -> String {
+    format!("'{}'", text.replace('\'', "'\\''"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn yaml_should_be_a_sequence_of_mappings() {
+        let records = [Record {
+            name: "a.txt".to_owned(),
+            source: None,
+            kind: "file",
+            size: Some(12),
+            modified: None,
+        }];
+
+        assert_eq!(
+            to_yaml(&records),
+            "- name: a.txt\n  type: file\n  size: 12\n  modified: null\n"
+        );
+    }
+
+    #[test]
+    fn json_line_should_escape_names_and_use_null() {
+        let record = Record {
+            name: "tab\there \"quoted\"\u{1}".to_owned(),
+            source: None,
+            kind: "file",
+            size: None,
+            modified: Some("2023-02-01T10:00:00+00:00".to_owned()),
+        };
+
+        assert_eq!(
+            to_json_line(&record),
+            "{\"name\":\"tab\\there \\\"quoted\\\"\\u0001\",\"type\":\"file\",\
+             \"size\":null,\"modified\":\"2023-02-01T10:00:00+00:00\"}\n"
+        );
+    }
+
+    #[test]
+    fn shell_words_should_survive_quotes_and_spaces() {
+        assert_eq!(
+            to_shell(["plain", "it's here", "-rf", "a\nb"]),
+            "'plain' 'it'\\''s here' '-rf' 'a\nb'\n"
+        );
+        assert_eq!(to_shell([]), "");
+    }
+
+    #[test]
+    fn json_tree_should_nest_children_only_where_read() {
+        let record = |name: &str, kind| Record {
+            name: name.to_owned(),
+            source: None,
+            kind,
+            size: Some(0),
+            modified: None,
+        };
+        let tree = Node {
+            record: record("root", "directory"),
+            children: Some(vec![
+                Node {
+                    record: record("empty", "directory"),
+                    children: Some(Vec::new()),
+                },
+                Node {
+                    record: record("file", "file"),
+                    children: None,
+                },
+            ]),
+        };
+
+        assert_eq!(
+            to_json_tree(&tree),
+            "{\"name\":\"root\",\"type\":\"directory\",\"size\":0,\"modified\":null,\"children\":[\
+             {\"name\":\"empty\",\"type\":\"directory\",\"size\":0,\"modified\":null,\"children\":[]},\
+             {\"name\":\"file\",\"type\":\"file\",\"size\":0,\"modified\":null}]}\n"
+        );
+    }
+
+    #[test]
+    fn toml_should_be_an_array_of_tables_without_missing_values() {
+        let records = [
+            Record {
+                name: "a \"quoted\" name".to_owned(),
+                source: None,
+                kind: "file",
+                size: Some(12),
+                modified: Some("2023-02-01T10:00:00+00:00".to_owned()),
+            },
+            Record {
+                name: "gone".to_owned(),
+                source: None,
+                kind: "file",
+                size: None,
+                modified: None,
+            },
+        ];
+
+        assert_eq!(
+            to_toml(&records),
+            "[[files]]\n\
+             name = 'a \"quoted\" name'\n\
+             type = \"file\"\n\
+             size = 12\n\
+             modified = \"2023-02-01T10:00:00+00:00\"\n\
+             \n\
+             [[files]]\n\
+             name = \"gone\"\n\
+             type = \"file\"\n"
+        );
+    }
+}
@@ -0,0 +1,32 @@
+use rand::{rngs::StdRng, seq::SliceRandom, SeedableRng};
+
+/// Puts `items` in a random order for `--sort=random`, the same one every
+/// time for the same `seed` and the same number of items.
+pub fn shuffle<T>(items: &mut [T], seed: u64) {
+    items.shuffle(&mut StdRng::seed_from_u64(seed));
+}
+
+/// A seed for when none is given, different from one run to the next.
+pub fn fresh_seed() -> u64 {
+    rand::random()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn same_seed_should_give_the_same_order() {
+        let shuffled = |seed| {
+            let mut items: Vec<u32> = (0..20).collect();
+            shuffle(&mut items, seed);
+            items
+        };
+
+        assert_eq!(shuffled(42), shuffled(42));
+        assert_ne!(shuffled(42), shuffled(43));
+        let mut sorted = shuffled(42);
+        sorted.sort();
+        assert_eq!(sorted, (0..20).collect::<Vec<_>>());
+    }
+}
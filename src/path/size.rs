@@ -0,0 +1,269 @@
+use clap::ValueEnum;
+use colored::{ColoredString, Colorize};
+
+pub(crate) const KILOBYTE: u64 = 1000;
+pub(crate) const MEGABYTE: u64 = 1000 * KILOBYTE;
+pub(crate) const GIGABYTE: u64 = 1000 * MEGABYTE;
+pub(crate) const TERABYTE: u64 = 1000 * GIGABYTE;
+
+/// Cells in a size bar.
+pub const BAR_WIDTH: usize = 8;
+
+/// How a size maps onto the length of its bar.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum BarScale {
+    /// In proportion to the size.
+    #[default]
+    Linear,
+    /// In proportion to the logarithm of the size, so one huge file doesn't
+    /// flatten all the others.
+    Log,
+}
+
+/// A bar of [`BAR_WIDTH`] cells filled in proportion to `bytes / max` on the
+/// given scale, e.g. `▇▇▇▁▁▁▁▁`. Anything non-empty gets at least one filled
+/// cell, and empty files none.
+pub fn bar(bytes: u64, max: u64, scale: BarScale) -> String {
+    let fraction = match scale {
+        BarScale::Linear => bytes as f64 / max as f64,
+        // Shifted by one so a single byte still counts for something
+        BarScale::Log => (bytes as f64).ln_1p() / (max as f64).ln_1p(),
+    };
+    let filled = if bytes == 0 || max == 0 {
+        0
+    } else {
+        ((fraction * BAR_WIDTH as f64).round() as usize).clamp(1, BAR_WIDTH)
+    };
+    "▇".repeat(filled) + &"▁".repeat(BAR_WIDTH - filled)
+}
+
+/// How unit suffixes are spelled.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum SizeStyle {
+    /// A single letter, e.g. `1K` or `2M`.
+    Short,
+    /// The full unit, e.g. `1KB` or `2MB`.
+    #[default]
+    Long,
+}
+
+/// Colors a file's size column by magnitude so large files stand out: bytes
+/// are dimmed, kilobytes plain, megabytes yellow and anything bigger red.
+pub fn colorize(text: &str, bytes: u64) -> ColoredString {
+    if bytes < KILOBYTE {
+        text.white().dimmed()
+    } else if bytes < MEGABYTE {
+        text.white()
+    } else if bytes < GIGABYTE {
+        text.yellow()
+    } else {
+        text.red()
+    }
+}
+
+/// Ends of the `--color-scale` gradient: small files green, the largest red.
+const SMALLEST: (u8, u8, u8) = (0x5f, 0xd7, 0x5f);
+const LARGEST: (u8, u8, u8) = (0xd7, 0x00, 0x00);
+
+/// Colors a size by where `bytes` falls between nothing and `max`, blending
+/// the two ends of the gradient in truecolor.
+pub fn gradient(text: &str, bytes: u64, max: u64) -> ColoredString {
+    let t = if max == 0 {
+        0.0
+    } else {
+        (bytes as f64 / max as f64).min(1.0)
+    };
+    let mix = |from: u8, to: u8| (from as f64 + (to as f64 - from as f64) * t).round() as u8;
+    text.truecolor(
+        mix(SMALLEST.0, LARGEST.0),
+        mix(SMALLEST.1, LARGEST.1),
+        mix(SMALLEST.2, LARGEST.2),
+    )
+}
+
+/// Whether the terminal says it draws 24-bit colors, the way most of them
+/// do: through `COLORTERM`.
+pub fn truecolor_supported() -> bool {
+    std::env::var("COLORTERM").is_ok_and(|value| value == "truecolor" || value == "24bit")
+}
+
+/// Fixed unit used to display every size, like `ls --block-size`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BlockSize {
+    pub unit: u64,
+    pub suffix: &'static str,
+}
+
+impl BlockSize {
+    /// Parses `K`, `M`, `G` (powers of 1024), `KB`, `MB`, `GB` (powers of
+    /// 1000) or a plain byte count, which is shown without a suffix.
+    pub fn parse(value: &str) -> Result<Self, String> {
+        let block_size = match value {
+            "K" => BlockSize::new(1 << 10, "K"),
+            "M" => BlockSize::new(1 << 20, "M"),
+            "G" => BlockSize::new(1 << 30, "G"),
+            "KB" => BlockSize::new(1000, "KB"),
+            "MB" => BlockSize::new(1000 * 1000, "MB"),
+            "GB" => BlockSize::new(1000 * 1000 * 1000, "GB"),
+            _ => match value.parse::<u64>() {
+                Ok(unit) if unit > 0 => BlockSize::new(unit, ""),
+                _ => {
+                    return Err(format!(
+                        "invalid block size '{value}' (expected K, M, G, KB, MB, GB or a positive number)"
+                    ))
+                }
+            },
+        };
+        Ok(block_size)
+    }
+
+    fn new(unit: u64, suffix: &'static str) -> Self {
+        BlockSize { unit, suffix }
+    }
+
+    /// Number of whole blocks needed for `size`, rounding up like `ls`.
+    pub fn format(&self, size: u64) -> String {
+        format!("{}{}", size.div_ceil(self.unit), self.suffix)
+    }
+}
+
+/// One unit for each size, picked by magnitude, the way GNU `ls -h` and
+/// `ls --si` print them.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HumanSize {
+    /// Powers of 1024 with `K`, `M`, `G`..., like `-h`.
+    Binary,
+    /// Powers of 1000 with `k`, `M`, `G`..., like `--si`.
+    Si,
+}
+
+impl HumanSize {
+    /// Formats `size` like GNU: plain bytes below one unit, one decimal below
+    /// ten, whole numbers above, always rounding up. A value that rounds up
+    /// to a full unit moves on to the next one, so 1048575 bytes is `1.0M`.
+    pub fn format(self, size: u64) -> String {
+        let (base, suffixes): (u128, [&str; 6]) = match self {
+            HumanSize::Binary => (1024, ["K", "M", "G", "T", "P", "E"]),
+            HumanSize::Si => (1000, ["k", "M", "G", "T", "P", "E"]),
+        };
+        let size = u128::from(size);
+        if size < base {
+            return size.to_string();
+        }
+        let (mut unit, mut divisor) = (0, base);
+        while size >= divisor * base && unit + 1 < suffixes.len() {
+            unit += 1;
+            divisor *= base;
+        }
+        let tenths = (size * 10).div_ceil(divisor);
+        if tenths < 100 {
+            return format!("{}.{}{}", tenths / 10, tenths % 10, suffixes[unit]);
+        }
+        let whole = size.div_ceil(divisor);
+        if whole >= base && unit + 1 < suffixes.len() {
+            format!("1.0{}", suffixes[unit + 1])
+        } else {
+            format!("{whole}{}", suffixes[unit])
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn block_size_should_parse_units() {
+        assert_eq!(BlockSize::parse("K").unwrap().unit, 1024);
+        assert_eq!(BlockSize::parse("MB").unwrap().unit, 1_000_000);
+        assert_eq!(BlockSize::parse("512").unwrap(), BlockSize::new(512, ""));
+    }
+
+    #[test]
+    fn block_size_should_reject_garbage() {
+        assert!(BlockSize::parse("X").is_err());
+        assert!(BlockSize::parse("0").is_err());
+    }
+
+    #[test]
+    fn colorize_should_follow_magnitude() {
+        colored::control::set_override(true);
+
+        assert_eq!(colorize("1B", 1), "1B".white().dimmed());
+        assert_eq!(colorize("2KB", 2_000), "2KB".white());
+        assert_eq!(colorize("3MB", 3_000_000), "3MB".yellow());
+        assert_eq!(colorize("4GB", 4_000_000_000), "4GB".red());
+    }
+
+    #[test]
+    fn bar_should_scale_to_max() {
+        assert_eq!(bar(100, 100, BarScale::Linear), "▇▇▇▇▇▇▇▇");
+        assert_eq!(bar(50, 100, BarScale::Linear), "▇▇▇▇▁▁▁▁");
+        assert_eq!(bar(1, 100, BarScale::Linear), "▇▁▁▁▁▁▁▁");
+        assert_eq!(bar(0, 100, BarScale::Linear), "▁▁▁▁▁▁▁▁");
+    }
+
+    #[test]
+    fn log_bar_should_keep_small_files_visible() {
+        let max = 1 << 30;
+
+        assert_eq!(bar(max, max, BarScale::Log), "▇▇▇▇▇▇▇▇");
+        // A megabyte next to a gigabyte: a sliver linearly, most of the way
+        // on a log scale
+        assert_eq!(bar(1 << 20, max, BarScale::Linear), "▇▁▁▁▁▁▁▁");
+        assert_eq!(bar(1 << 20, max, BarScale::Log), "▇▇▇▇▇▁▁▁");
+        assert_eq!(bar(1 << 10, max, BarScale::Log), "▇▇▇▁▁▁▁▁");
+        assert_eq!(bar(0, max, BarScale::Log), "▁▁▁▁▁▁▁▁");
+    }
+
+    #[test]
+    fn gradient_should_blend_between_the_ends() {
+        colored::control::set_override(true);
+
+        assert_eq!(gradient("x", 0, 100), "x".truecolor(0x5f, 0xd7, 0x5f));
+        assert_eq!(gradient("x", 50, 100), "x".truecolor(0x9b, 0x6c, 0x30));
+        assert_eq!(gradient("x", 100, 100), "x".truecolor(0xd7, 0x00, 0x00));
+    }
+
+    #[test]
+    fn block_size_should_round_up() {
+        assert_eq!(BlockSize::parse("K").unwrap().format(1025), "2K");
+        assert_eq!(BlockSize::parse("512").unwrap().format(512), "1");
+    }
+
+    #[test]
+    fn human_binary_should_match_gnu() {
+        let sizes = [
+            (0, "0"),
+            (1023, "1023"),
+            (1024, "1.0K"),
+            (1025, "1.1K"),
+            (1536, "1.5K"),
+            (10239, "10K"),
+            (10240, "10K"),
+            (10241, "11K"),
+            (1048575, "1.0M"),
+            (5 << 30, "5.0G"),
+        ];
+        for (bytes, expected) in sizes {
+            assert_eq!(HumanSize::Binary.format(bytes), expected, "{bytes}");
+        }
+    }
+
+    #[test]
+    fn human_si_should_match_gnu() {
+        let sizes = [
+            (999, "999"),
+            (1000, "1.0k"),
+            (1001, "1.1k"),
+            (1100, "1.1k"),
+            (2_300_000, "2.3M"),
+            (123_456_789, "124M"),
+            (999_999, "1.0M"),
+            (4_000_000_000, "4.0G"),
+        ];
+        for (bytes, expected) in sizes {
+            assert_eq!(HumanSize::Si.format(bytes), expected, "{bytes}");
+        }
+    }
+}
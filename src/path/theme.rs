@@ -0,0 +1,199 @@
+use super::{
+    diff::Change,
+    size::{self, GIGABYTE, MEGABYTE},
+};
+use clap::ValueEnum;
+use colored::{Color, ColoredString, Colorize};
+
+/// Ready-made color sets for the parts of a listing.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum Theme {
+    /// Blue directories, white files and cyan times, for dark backgrounds.
+    #[default]
+    Dark,
+    /// Colors that keep their contrast on a white background.
+    Light,
+    /// Bold, underline and reverse only, with no color at all.
+    Mono,
+}
+
+impl Theme {
+    pub fn directory(self, text: &str) -> ColoredString {
+        match self {
+            Theme::Dark => text.blue(),
+            Theme::Light => text.blue().bold(),
+            Theme::Mono => text.bold(),
+        }
+    }
+
+    pub fn file(self, text: &str) -> ColoredString {
+        match self {
+            Theme::Dark => text.white(),
+            Theme::Light | Theme::Mono => text.normal(),
+        }
+    }
+
+    /// A symlink whose target is gone.
+    pub fn broken(self, text: &str) -> ColoredString {
+        match self {
+            Theme::Dark | Theme::Light => text.red(),
+            Theme::Mono => text.reversed(),
+        }
+    }
+
+    pub fn time(self, text: &str) -> ColoredString {
+        match self {
+            Theme::Dark => text.bright_cyan(),
+            Theme::Light => text.magenta(),
+            Theme::Mono => text.normal(),
+        }
+    }
+
+    /// The time of an entry changed within `--recent`, set apart from the
+    /// others.
+    pub fn recent(self, text: &str) -> ColoredString {
+        match self {
+            Theme::Dark => text.bright_green().bold(),
+            Theme::Light => text.green().bold(),
+            Theme::Mono => text.bold(),
+        }
+    }
+
+    /// A name `--watch --diff` found new, changed or gone since the last
+    /// refresh.
+    pub fn change(self, text: &str, change: Change) -> ColoredString {
+        match (self, change) {
+            (Theme::Dark, Change::Added) => text.bright_green(),
+            (Theme::Dark, Change::Modified) => text.bright_yellow(),
+            (Theme::Light, Change::Added) => text.green(),
+            // Yellow is hard to read on white
+            (Theme::Light, Change::Modified) => text.magenta(),
+            (Theme::Dark | Theme::Light, Change::Removed) => text.red().strikethrough(),
+            (Theme::Mono, Change::Added) => text.bold(),
+            (Theme::Mono, Change::Modified) => text.underline(),
+            (Theme::Mono, Change::Removed) => text.strikethrough(),
+        }
+    }
+
+    /// A file named with `extension`, in one of a few colors picked by the
+    /// extension itself, so files of a kind share a color from run to run.
+    /// Blue is left to directories.
+    pub fn extension(self, text: &str, extension: &str) -> ColoredString {
+        let palette: &[Color] = match self {
+            Theme::Dark => &[
+                Color::Green,
+                Color::Yellow,
+                Color::Magenta,
+                Color::Cyan,
+                Color::Red,
+                Color::BrightGreen,
+                Color::BrightYellow,
+                Color::BrightMagenta,
+            ],
+            Theme::Light => &[Color::Green, Color::Magenta, Color::Red, Color::Cyan],
+            Theme::Mono => return text.normal(),
+        };
+        // FNV-1a, which unlike the std hasher is sure to stay the same
+        let hash = extension
+            .bytes()
+            .fold(0xcbf2_9ce4_8422_2325_u64, |hash, byte| {
+                (hash ^ u64::from(byte)).wrapping_mul(0x0100_0000_01b3)
+            });
+        text.color(palette[(hash % palette.len() as u64) as usize])
+    }
+
+    /// A file's size, marked by magnitude so large files stand out.
+    pub fn size(self, text: &str, bytes: u64) -> ColoredString {
+        match self {
+            Theme::Dark => size::colorize(text, bytes),
+            // Yellow fades into white, so megabytes go magenta
+            Theme::Light if bytes < MEGABYTE => text.normal(),
+            Theme::Light if bytes < GIGABYTE => text.magenta(),
+            Theme::Light => text.red(),
+            Theme::Mono if bytes < MEGABYTE => text.normal(),
+            Theme::Mono if bytes < GIGABYTE => text.underline(),
+            Theme::Mono => text.bold(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Whether `text` picks a foreground or background color, not just an
+    /// attribute like bold (1), underline (4) or reverse (7).
+    fn has_color(text: &ColoredString) -> bool {
+        text.fgcolor().is_some() || text.bgcolor().is_some()
+    }
+
+    #[test]
+    fn mono_should_use_attributes_only() {
+        let styled = [
+            Theme::Mono.directory("d"),
+            Theme::Mono.file("f"),
+            Theme::Mono.broken("b"),
+            Theme::Mono.time("t"),
+            Theme::Mono.recent("r"),
+            Theme::Mono.size("s", 1),
+            Theme::Mono.size("s", 5 * MEGABYTE),
+            Theme::Mono.size("s", 5 * GIGABYTE),
+            Theme::Mono.extension("e", "rs"),
+            Theme::Mono.change("c", Change::Added),
+            Theme::Mono.change("c", Change::Modified),
+            Theme::Mono.change("c", Change::Removed),
+        ];
+
+        assert!(!styled.iter().any(has_color));
+        assert_eq!(Theme::Mono.directory("d"), "d".bold());
+        assert_eq!(Theme::Mono.broken("b"), "b".reversed());
+    }
+
+    #[test]
+    fn light_should_avoid_white_and_yellow() {
+        let styled = [
+            Theme::Light.directory("d"),
+            Theme::Light.file("f"),
+            Theme::Light.time("t"),
+            Theme::Light.recent("r"),
+            Theme::Light.size("s", 1),
+            Theme::Light.size("s", 5 * MEGABYTE),
+            Theme::Light.size("s", 5 * GIGABYTE),
+            Theme::Light.extension("e", "rs"),
+            Theme::Light.extension("e", "toml"),
+            Theme::Light.extension("e", "md"),
+            Theme::Light.change("c", Change::Modified),
+        ];
+
+        let washed_out = [
+            Color::White,
+            Color::BrightWhite,
+            Color::Yellow,
+            Color::BrightYellow,
+        ];
+        assert!(!styled.iter().any(|text| text
+            .fgcolor()
+            .is_some_and(|color| washed_out.contains(&color))));
+    }
+
+    #[test]
+    fn extensions_should_keep_their_color() {
+        let color = |extension| Theme::Dark.extension("name", extension).fgcolor();
+
+        assert_eq!(color("rs"), color("rs"));
+        assert!(["rs", "toml", "md", "txt", "png"]
+            .iter()
+            .all(|extension| color(extension).is_some_and(|color| color != Color::Blue)));
+    }
+
+    #[test]
+    fn dark_should_keep_the_original_colors() {
+        assert_eq!(Theme::Dark.directory("d"), "d".blue());
+        assert_eq!(Theme::Dark.file("f"), "f".white());
+        assert_eq!(Theme::Dark.time("t"), "t".bright_cyan());
+        assert_eq!(
+            Theme::Dark.size("s", 2 * GIGABYTE),
+            size::colorize("s", 2 * GIGABYTE)
+        );
+    }
+}
@@ -0,0 +1,190 @@
+use chrono::{DateTime, Local, Utc};
+use clap::ValueEnum;
+use colored::Color;
+use std::time::{Duration, SystemTime};
+
+const DAY: Duration = Duration::from_secs(24 * 60 * 60);
+
+/// How timestamps are written.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum TimeStyle {
+    /// Day, month and time to the minute, e.g. `30 Jan 20:37`.
+    #[default]
+    Default,
+    /// Everything down to the millisecond plus the offset, like
+    /// `ls --full-time`, e.g. `2023-01-30 20:37:54.521 +0000`.
+    FullIso,
+    /// ISO 8601 to the second, for other programs to read, e.g.
+    /// `2023-01-30T20:37:54+00:00`.
+    Iso8601,
+}
+
+/// Which of an entry's timestamps is shown and sorted by, like `ls --time`.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum TimeKind {
+    /// When the contents last changed.
+    #[default]
+    #[value(alias = "mtime")]
+    Modification,
+    /// When the file was last read.
+    #[value(alias = "atime", alias = "use")]
+    Access,
+    /// When the contents or the metadata last changed, e.g. permissions.
+    #[value(alias = "ctime", alias = "status")]
+    Change,
+}
+
+/// How finely the default style writes the time of day.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum Precision {
+    /// `20:37`
+    #[default]
+    Min,
+    /// `20:37:54`
+    Sec,
+    /// `20:37:54.521`
+    Ms,
+}
+
+impl TimeStyle {
+    /// The other styles have a fixed resolution, so only the default one
+    /// looks at `precision`.
+    fn pattern(self, precision: Precision) -> &'static str {
+        match (self, precision) {
+            (TimeStyle::Default, Precision::Min) => "%e %b %R",
+            (TimeStyle::Default, Precision::Sec) => "%e %b %T",
+            (TimeStyle::Default, Precision::Ms) => "%e %b %T%.3f",
+            (TimeStyle::FullIso, _) => "%Y-%m-%d %H:%M:%S%.3f %z",
+            (TimeStyle::Iso8601, _) => "%Y-%m-%dT%H:%M:%S%:z",
+        }
+    }
+}
+
+/// Formats `time` in the local timezone, or in UTC when `utc` is set.
+pub fn format(time: SystemTime, style: TimeStyle, precision: Precision, utc: bool) -> String {
+    with_pattern(time, style.pattern(precision), utc)
+}
+
+/// Just the day, e.g. `2023-01-30`.
+pub fn date(time: SystemTime, utc: bool) -> String {
+    with_pattern(time, "%Y-%m-%d", utc)
+}
+
+fn with_pattern(time: SystemTime, pattern: &str, utc: bool) -> String {
+    if utc {
+        DateTime::<Utc>::from(time).format(pattern).to_string()
+    } else {
+        DateTime::<Local>::from(time).format(pattern).to_string()
+    }
+}
+
+/// Parses a span of time like `90s`, `15m`, `2h`, `3d` or `1w`.
+pub fn parse_duration(value: &str) -> Result<Duration, String> {
+    let invalid = || format!("invalid duration '{value}' (expected e.g. 30s, 15m, 2h, 3d or 1w)");
+    let split = value
+        .find(|c: char| !c.is_ascii_digit())
+        .ok_or_else(invalid)?;
+    let (amount, unit) = value.split_at(split);
+    let amount: u64 = amount.parse().map_err(|_| invalid())?;
+    let seconds = match unit {
+        "s" => 1,
+        "m" => 60,
+        "h" => 60 * 60,
+        "d" => DAY.as_secs(),
+        "w" => 7 * DAY.as_secs(),
+        _ => return Err(invalid()),
+    };
+    amount
+        .checked_mul(seconds)
+        .map(Duration::from_secs)
+        .ok_or_else(invalid)
+}
+
+/// Coarse age of a timestamp, used to color the time column as a heatmap.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum Age {
+    Today,
+    ThisWeek,
+    ThisMonth,
+    Older,
+}
+
+impl Age {
+    /// Timestamps in the future (clock skew) count as today.
+    pub fn of(time: SystemTime, now: SystemTime) -> Self {
+        let age = now.duration_since(time).unwrap_or_default();
+        if age < DAY {
+            Age::Today
+        } else if age < 7 * DAY {
+            Age::ThisWeek
+        } else if age < 30 * DAY {
+            Age::ThisMonth
+        } else {
+            Age::Older
+        }
+    }
+
+    pub fn color(self) -> Color {
+        match self {
+            Age::Today => Color::BrightGreen,
+            Age::ThisWeek => Color::Green,
+            Age::ThisMonth => Color::Yellow,
+            Age::Older => Color::Red,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn durations_should_parse_each_unit() {
+        assert_eq!(parse_duration("90s"), Ok(Duration::from_secs(90)));
+        assert_eq!(parse_duration("15m"), Ok(Duration::from_secs(15 * 60)));
+        assert_eq!(parse_duration("2h"), Ok(Duration::from_secs(2 * 60 * 60)));
+        assert_eq!(parse_duration("1w"), Ok(7 * DAY));
+        for bad in ["", "15", "m", "1.5h", "2y", "-1h"] {
+            assert!(parse_duration(bad).is_err(), "{bad}");
+        }
+    }
+
+    #[test]
+    fn full_iso_should_keep_milliseconds_and_offset() {
+        // Mon Jan 30 2023 20:37:54.521 UTC+0
+        let time = SystemTime::UNIX_EPOCH + Duration::from_millis(1675111074521);
+
+        assert_eq!(
+            format(time, TimeStyle::FullIso, Precision::Min, true),
+            "2023-01-30 20:37:54.521 +0000"
+        );
+    }
+
+    #[test]
+    fn precision_should_keep_seconds_or_milliseconds() {
+        // Mon Jan 30 2023 20:37:54.521 UTC+0
+        let time = SystemTime::UNIX_EPOCH + Duration::from_millis(1675111074521);
+        let default = |precision| format(time, TimeStyle::Default, precision, true);
+
+        assert_eq!(default(Precision::Min), "30 Jan 20:37");
+        assert_eq!(default(Precision::Sec), "30 Jan 20:37:54");
+        assert_eq!(default(Precision::Ms), "30 Jan 20:37:54.521");
+    }
+
+    #[test]
+    fn age_should_pick_bucket() {
+        let now = SystemTime::UNIX_EPOCH + 365 * DAY;
+
+        assert_eq!(Age::of(now - Duration::from_secs(60), now), Age::Today);
+        assert_eq!(Age::of(now - 3 * DAY, now), Age::ThisWeek);
+        assert_eq!(Age::of(now - 20 * DAY, now), Age::ThisMonth);
+        assert_eq!(Age::of(now - 200 * DAY, now), Age::Older);
+    }
+
+    #[test]
+    fn future_time_should_be_today() {
+        let now = SystemTime::UNIX_EPOCH + DAY;
+
+        assert_eq!(Age::of(now + DAY, now), Age::Today);
+    }
+}
@@ -0,0 +1,284 @@
+use super::{
+    listing::{list_directory, EntryError},
+    options::Options,
+    paths::{Path, Paths},
+    record::Node,
+};
+use std::io;
+
+/// Everything below `root` as one listing, each entry drawn under its parent
+/// with `tree`-style branches. The entries are filtered and ordered a
+/// directory at a time, with the same rules as a flat listing, so the shape
+/// doesn't depend on the order the filesystem returns names in. The root
+/// itself is not an entry. With `dirs_only`, files are left out at every
+/// level, and `max_depth` keeps it from going deeper than that many levels.
+/// With `tree_relative`, each entry is named by its path from the root; the
+/// branches still only go by depth.
+///
+/// Only failing to read `root` is an error. Subdirectories that can't be read
+/// are kept as leaves and reported in `Paths::errors`.
+pub fn list_tree(root: &std::path::Path, options: &Options) -> io::Result<Paths> {
+    list_tree_with(root, options, &mut |_| {})
+}
+
+/// Like `list_tree`, calling `scanned` with the number of entries of each
+/// directory as it is read, e.g. to show progress on a big tree.
+pub fn list_tree_with(
+    root: &std::path::Path,
+    options: &Options,
+    scanned: &mut impl FnMut(usize),
+) -> io::Result<Paths> {
+    let mut tree = Paths::with_options(options.clone());
+    let level = list_directory(root, options)?;
+    add_level(&mut tree, level, 1, "", std::path::Path::new(""), scanned);
+    Ok(tree)
+}
+
+/// Everything below `root` as nested nodes, for `--tree --json`, with
+/// `root` itself at the top under the name it was given. Entries are
+/// filtered, ordered and cut off at `max_depth` like in `list_tree`, and
+/// directories that can't be read are reported in the errors returned.
+pub fn tree_nodes(
+    root: &std::path::Path,
+    options: &Options,
+) -> io::Result<(Node, Vec<EntryError>)> {
+    let level = list_directory(root, options)?;
+    let record = Path::from_file(root, true)?.record();
+    let mut errors = Vec::new();
+    let children = nodes(level, options, 1, &mut errors);
+    let node = Node {
+        record,
+        children: Some(children),
+    };
+    Ok((node, errors))
+}
+
+fn nodes(
+    mut level: Paths,
+    options: &Options,
+    depth: usize,
+    errors: &mut Vec<EntryError>,
+) -> Vec<Node> {
+    let deeper = options.max_depth.is_none_or(|max| depth < max);
+    errors.append(&mut level.errors);
+    let mut children = level.ordered();
+    if options.dirs_only {
+        children.retain(Path::is_dir);
+    }
+    children
+        .into_iter()
+        .map(|child| {
+            let below = (deeper && child.is_dir() && !child.is_symlink())
+                .then(|| match list_directory(child.full_path(), options) {
+                    Ok(level) => Some(nodes(level, options, depth + 1, errors)),
+                    Err(error) => {
+                        errors.push(EntryError {
+                            name: child.full_path().to_string_lossy().into_owned(),
+                            error,
+                        });
+                        None
+                    }
+                })
+                .flatten();
+            Node {
+                record: child.record(),
+                children: below,
+            }
+        })
+        .collect()
+}
+
+fn add_level(
+    tree: &mut Paths,
+    mut level: Paths,
+    depth: usize,
+    indent: &str,
+    relative: &std::path::Path,
+    scanned: &mut impl FnMut(usize),
+) {
+    let deeper = tree.options.max_depth.is_none_or(|max| depth < max);
+    scanned(level.paths.len());
+    tree.errors.append(&mut level.errors);
+    let mut children = level.ordered();
+    if tree.options.dirs_only {
+        children.retain(|child| child.is_dir());
+    }
+    let count = children.len();
+    for (i, mut child) in children.into_iter().enumerate() {
+        let last = i + 1 == count;
+        child.set_branch(format!("{indent}{}", if last { "└── " } else { "├── " }));
+        let path = relative.join(child.raw_name());
+        if tree.options.tree_relative {
+            child.set_name(path.to_string_lossy().into_owned());
+        }
+        let below = (deeper && child.is_dir() && !child.is_symlink())
+            .then(|| child.full_path().to_path_buf());
+        tree.paths.push(child);
+        let Some(dir) = below else {
+            continue;
+        };
+        match list_directory(&dir, &tree.options) {
+            Ok(level) => {
+                let indent = format!("{indent}{}", if last { "    " } else { "│   " });
+                add_level(tree, level, depth + 1, &indent, &path, scanned);
+            }
+            Err(error) => tree.errors.push(EntryError {
+                name: dir.to_string_lossy().into_owned(),
+                error,
+            }),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::path::layout::{plain, Scratch};
+    use std::fs;
+
+    const LAYOUT: &[&str] = &[".hidden/", "c", "a", "b/z", "b/y", "b/inner/x"];
+
+    fn render(root: &std::path::Path, reverse: bool) -> String {
+        let options = Options {
+            reverse,
+            ..Default::default()
+        };
+        render_with(root, options)
+    }
+
+    fn render_with(root: &std::path::Path, options: Options) -> String {
+        let options = Options {
+            tree: Some(root.to_string_lossy().into_owned()),
+            ..options
+        };
+        plain(&list_tree(root, &options).unwrap().render())
+    }
+
+    #[test]
+    fn tree_should_be_alphabetical_and_stable() {
+        let root = Scratch::new("lsr_tree_stable", LAYOUT);
+
+        let first = render(&root, false);
+        let second = render(&root, false);
+
+        assert_eq!(first, second);
+        assert_eq!(
+            first,
+            "├── a\n\
+             ├── b\n\
+             │   ├── inner\n\
+             │   │   └── x\n\
+             │   ├── y\n\
+             │   └── z\n\
+             └── c\n"
+        );
+    }
+
+    #[test]
+    fn reverse_should_flip_each_directory() {
+        let root = Scratch::new("lsr_tree_reverse", LAYOUT);
+
+        let out = render(&root, true);
+
+        assert_eq!(
+            out,
+            "├── c\n\
+             ├── b\n\
+             │   ├── z\n\
+             │   ├── y\n\
+             │   └── inner\n\
+             │       └── x\n\
+             └── a\n"
+        );
+    }
+
+    #[test]
+    fn dirs_only_should_drop_files_and_keep_empty_dirs() {
+        let root = Scratch::new("lsr_tree_dirs_only", LAYOUT);
+        fs::create_dir_all(root.join("b").join("empty")).unwrap();
+        fs::create_dir_all(root.join("d")).unwrap();
+        let options = Options {
+            dirs_only: true,
+            ..Default::default()
+        };
+
+        let out = render_with(&root, options);
+
+        assert_eq!(
+            out,
+            "├── b\n\
+             │   ├── empty\n\
+             │   └── inner\n\
+             └── d\n"
+        );
+    }
+
+    #[test]
+    fn tree_relative_should_name_entries_from_the_root() {
+        let root = Scratch::new("lsr_tree_relative", LAYOUT);
+        let options = Options {
+            tree_relative: true,
+            ..Default::default()
+        };
+
+        let out = render_with(&root, options);
+
+        assert_eq!(
+            out,
+            "├── a\n\
+             ├── b\n\
+             │   ├── b/inner\n\
+             │   │   └── b/inner/x\n\
+             │   ├── b/y\n\
+             │   └── b/z\n\
+             └── c\n"
+        );
+    }
+
+    #[test]
+    fn tree_nodes_should_follow_the_tree_rules() {
+        let root = Scratch::new("lsr_tree_nodes", LAYOUT);
+        let options = Options {
+            max_depth: Some(2),
+            ignore: vec![glob::Pattern::new("c").unwrap()],
+            ..Default::default()
+        };
+
+        let (tree, errors) = tree_nodes(&root, &options).unwrap();
+
+        let names = |nodes: &[Node]| -> Vec<String> {
+            nodes.iter().map(|node| node.record.name.clone()).collect()
+        };
+        assert!(errors.is_empty());
+        assert_eq!(tree.record.name, root.to_string_lossy());
+        let top = tree.children.unwrap();
+        assert_eq!(names(&top), ["a", "b"]);
+        assert_eq!(top[0].children, None);
+        let b = top[1].children.as_ref().unwrap();
+        assert_eq!(names(b), ["inner", "y", "z"]);
+        // Past the depth limit a directory's contents are not read
+        assert_eq!(b[0].record.kind, "directory");
+        assert_eq!(b[0].children, None);
+    }
+
+    #[test]
+    fn max_depth_should_keep_deeper_dirs_as_leaves() {
+        let root = Scratch::new("lsr_tree_max_depth", LAYOUT);
+        let options = Options {
+            max_depth: Some(2),
+            ..Default::default()
+        };
+
+        let out = render_with(&root, options);
+
+        assert_eq!(
+            out,
+            "├── a\n\
+             ├── b\n\
+             │   ├── inner\n\
+             │   ├── y\n\
+             │   └── z\n\
+             └── c\n"
+        );
+    }
+}
@@ -0,0 +1,231 @@
+use super::{options::Options, paths, vfs};
+use std::{
+    collections::HashSet,
+    fs::{self, Metadata},
+    io,
+    path::{Path, PathBuf},
+};
+
+/// Adds up what a directory holds, like `du`. Each file is counted once
+/// however many hard links lead to it, no directory is entered twice, and
+/// symlinks are counted as themselves rather than followed, so neither
+/// hardlinks nor cycles can inflate the total or keep the walk going.
+#[derive(Debug, Default)]
+pub struct Usage {
+    actual: bool,
+    seen: HashSet<(u64, u64)>,
+}
+
+impl Usage {
+    /// With `actual`, files count the space allocated on disk rather than
+    /// their length.
+    pub fn new(actual: bool) -> Self {
+        Usage {
+            actual,
+            seen: HashSet::new(),
+        }
+    }
+
+    /// Bytes of what `path` names: the file itself, or everything below it
+    /// for a directory, like `du -s`. Only failing to look at `path` at all
+    /// is an error.
+    pub fn total(&mut self, path: &Path) -> io::Result<u64> {
+        let metadata = fs::symlink_metadata(path)?;
+        if !self.first_visit(&metadata) {
+            Ok(0)
+        } else if metadata.is_dir() {
+            Ok(self.of(path))
+        } else {
+            Ok(self.bytes(&metadata))
+        }
+    }
+
+    /// Bytes of every file below `dir` not counted by this `Usage` before.
+    /// Directories themselves add nothing, and ones that can't be read are
+    /// skipped, as are virtual filesystems like `/proc`, which hold no data.
+    pub fn of(&mut self, dir: &Path) -> u64 {
+        if vfs::is_virtual(dir) {
+            return 0;
+        }
+        let Ok(entries) = fs::read_dir(dir) else {
+            return 0;
+        };
+        let mut total = 0;
+        for entry in entries.flatten() {
+            let Ok(metadata) = entry.metadata() else {
+                continue;
+            };
+            if !self.first_visit(&metadata) {
+                continue;
+            }
+            total += if metadata.is_dir() {
+                self.of(&entry.path())
+            } else {
+                self.bytes(&metadata)
+            };
+        }
+        total
+    }
+
+    fn first_visit(&mut self, metadata: &Metadata) -> bool {
+        match identity(metadata) {
+            Some(id) => self.seen.insert(id),
+            None => true,
+        }
+    }
+
+    #[cfg(unix)]
+    fn bytes(&self, metadata: &Metadata) -> u64 {
+        use std::os::unix::fs::MetadataExt;
+
+        if self.actual {
+            metadata.blocks() * 512
+        } else {
+            metadata.len()
+        }
+    }
+
+    #[cfg(not(unix))]
+    fn bytes(&self, metadata: &Metadata) -> u64 {
+        metadata.len()
+    }
+}
+
+/// The `--summarize` line for `path`, e.g. `12MB\tsrc`, with the size
+/// written the way the listing would write it. Files already counted for an
+/// earlier argument count for nothing, as with `du`.
+pub fn summary(usage: &mut Usage, path: &Path, options: &Options) -> io::Result<String> {
+    let bytes = usage.total(path)?;
+    let size = paths::Path::size_string_formatter(bytes, options);
+    Ok(format!("{size}\t{}", path.display()))
+}
+
+/// The `--group-sizes` footer, e.g. `src: 1.2MB, tests: 340KB`: what each
+/// directory argument holds, or with just one, each directory right inside
+/// it. Every group is measured on its own and the biggest comes first, with
+/// ties going by name.
+pub fn groups(dirs: &[&Path], options: &Options) -> String {
+    let groups: Vec<(String, PathBuf)> = match dirs {
+        [dir] => fs::read_dir(dir)
+            .into_iter()
+            .flatten()
+            .flatten()
+            .filter(|entry| entry.file_type().is_ok_and(|kind| kind.is_dir()))
+            .map(|entry| {
+                (
+                    entry.file_name().to_string_lossy().into_owned(),
+                    entry.path(),
+                )
+            })
+            .collect(),
+        _ => dirs
+            .iter()
+            .map(|dir| (dir.display().to_string(), dir.to_path_buf()))
+            .collect(),
+    };
+    let mut sizes: Vec<(u64, String)> = groups
+        .into_iter()
+        .map(|(name, dir)| (Usage::new(options.actual_size).of(&dir), name))
+        .collect();
+    sizes.sort_by(|(a_bytes, a), (b_bytes, b)| b_bytes.cmp(a_bytes).then_with(|| a.cmp(b)));
+    sizes
+        .iter()
+        .map(|(bytes, name)| {
+            let size = paths::Path::size_string_formatter(*bytes, options);
+            format!("{name}: {size}")
+        })
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// The device and inode, which every name of the same file shares.
+#[cfg(unix)]
+pub(crate) fn identity(metadata: &Metadata) -> Option<(u64, u64)> {
+    use std::os::unix::fs::MetadataExt;
+
+    Some((metadata.dev(), metadata.ino()))
+}
+
+/// Without inodes every name counts, like `du` on such systems.
+#[cfg(not(unix))]
+pub(crate) fn identity(_metadata: &Metadata) -> Option<(u64, u64)> {
+    None
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+    use crate::path::layout::Scratch;
+
+    fn layout(name: &str) -> Scratch {
+        let root = Scratch::new(name, &["sub/"]);
+        fs::write(root.join("file"), vec![0; 100]).unwrap();
+        fs::write(root.join("sub").join("other"), vec![0; 10]).unwrap();
+        root
+    }
+
+    #[test]
+    fn hardlinked_file_should_be_counted_once() {
+        let root = layout("lsr_usage_hardlink");
+        fs::hard_link(root.join("file"), root.join("sub").join("again")).unwrap();
+
+        let total = Usage::new(false).of(&root);
+
+        assert_eq!(total, 110);
+    }
+
+    #[test]
+    fn summary_should_use_the_size_options() {
+        let root = layout("lsr_usage_summary");
+        let bytes = Options {
+            raw_bytes: true,
+            ..Default::default()
+        };
+
+        let dir = summary(&mut Usage::new(false), &root, &bytes).unwrap();
+        let file = summary(&mut Usage::new(false), &root.join("file"), &bytes).unwrap();
+        let again = {
+            let mut usage = Usage::new(false);
+            usage.total(&root.join("file")).unwrap();
+            usage.total(&root).unwrap()
+        };
+
+        assert_eq!(dir, format!("110\t{}", root.display()));
+        assert_eq!(file, format!("100\t{}/file", root.display()));
+        // Counted once across arguments
+        assert_eq!(again, 10);
+    }
+
+    #[test]
+    fn groups_should_put_the_biggest_first() {
+        let root = layout("lsr_usage_groups");
+        fs::create_dir(root.join("big")).unwrap();
+        fs::write(root.join("big").join("data"), vec![0; 500]).unwrap();
+        fs::create_dir(root.join("empty")).unwrap();
+        let bytes = Options {
+            raw_bytes: true,
+            ..Default::default()
+        };
+
+        let inside = groups(&[&root], &bytes);
+        let arguments = groups(&[&root.join("sub"), &root.join("big")], &bytes);
+
+        // Files right inside the one argument belong to no group
+        assert_eq!(inside, "big: 500, sub: 10, empty: 0");
+        assert_eq!(
+            arguments,
+            format!("{0}/big: 500, {0}/sub: 10", root.display())
+        );
+    }
+
+    #[test]
+    fn symlinked_directory_should_not_be_followed() {
+        let root = layout("lsr_usage_cycle");
+        std::os::unix::fs::symlink("..", root.join("sub").join("up")).unwrap();
+
+        let total = Usage::new(false).of(&root);
+
+        // The link itself is two bytes long, the target ".."
+        assert_eq!(total, 112);
+    }
+}
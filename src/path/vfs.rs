@@ -0,0 +1,60 @@
+use std::path::Path;
+
+/// Filesystems whose files are made up by the kernel as they are read, e.g.
+/// procfs and sysfs, found by the magic number `statfs` reports.
+#[cfg(target_os = "linux")]
+const VIRTUAL: [u32; 11] = [
+    0x9fa0,      // proc
+    0x6265_6572, // sysfs
+    0x6462_6720, // debugfs
+    0x7472_6163, // tracefs
+    0x7363_6673, // securityfs
+    0x0027_e0eb, // cgroup
+    0x6367_7270, // cgroup2
+    0xcafe_4a11, // bpf
+    0x6265_6570, // configfs
+    0x6165_676c, // pstore
+    0xde5e_81e4, // efivarfs
+];
+
+/// Whether `path` is on a virtual filesystem like `/proc` or `/sys`. Their
+/// lengths are mostly 0 whatever reading gives, and reading can block, so
+/// sizes and contents there are left alone. A path that can't be asked
+/// about is taken to be a real one.
+#[cfg(target_os = "linux")]
+pub fn is_virtual(path: &Path) -> bool {
+    use std::{ffi::CString, os::unix::ffi::OsStrExt};
+
+    let Ok(path) = CString::new(path.as_os_str().as_bytes()) else {
+        return false;
+    };
+    // SAFETY: statfs only writes into the struct we hand it, and `path` is a
+    // NUL-terminated string that outlives the call.
+    unsafe {
+        let mut stats: libc::statfs = std::mem::zeroed();
+        libc::statfs(path.as_ptr(), &mut stats) == 0 && {
+            // The type of f_type differs between targets, but every magic
+            // number fits in 32 bits
+            VIRTUAL.contains(&(stats.f_type as u32))
+        }
+    }
+}
+
+/// Elsewhere there's no `/proc` to worry about.
+#[cfg(not(target_os = "linux"))]
+pub fn is_virtual(_path: &Path) -> bool {
+    false
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn proc_should_be_virtual() {
+        assert!(is_virtual(Path::new("/proc/self/status")));
+        assert!(!is_virtual(&std::env::temp_dir()));
+        assert!(!is_virtual(Path::new("does-not-exist")));
+    }
+}
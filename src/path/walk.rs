@@ -0,0 +1,183 @@
+use super::{
+    listing::list_directory,
+    options::{Options, Traversal},
+    paths::Paths,
+};
+use std::{collections::VecDeque, fs, io, path::PathBuf};
+
+/// One directory reached by a recursive walk.
+#[derive(Debug)]
+pub struct Visit {
+    /// The directory, as the root argument joined with the names below it.
+    pub path: PathBuf,
+    /// 0 for the root, 1 for its subdirectories and so on.
+    pub depth: usize,
+    pub listing: io::Result<Paths>,
+}
+
+/// Lists `root` and every directory below it, like `ls -R`. Directories are
+/// visited depth-first or breadth-first per `Options::traversal`, in the
+/// listing's sort order, and hidden ones only with `all`. Symlinks to
+/// directories are only descended into with `follow_symlinks`, and then a
+/// link back into a directory on the way down to it is marked as a loop in
+/// its listing instead. Other directories reached twice, e.g. through a
+/// link to a sibling, are listed both times. `max_depth` stops the walk from
+/// listing directories any deeper.
+pub struct Walk {
+    options: Options,
+    pending: VecDeque<Pending>,
+}
+
+/// A directory still to be listed, with the real paths of the directories
+/// above it, root first.
+struct Pending {
+    path: PathBuf,
+    depth: usize,
+    ancestors: Vec<PathBuf>,
+}
+
+impl Walk {
+    pub fn new(root: impl Into<PathBuf>, options: &Options) -> Self {
+        Walk {
+            options: options.clone(),
+            pending: VecDeque::from([Pending {
+                path: root.into(),
+                depth: 0,
+                ancestors: Vec::new(),
+            }]),
+        }
+    }
+}
+
+impl Iterator for Walk {
+    type Item = Visit;
+
+    fn next(&mut self) -> Option<Visit> {
+        let Pending {
+            path,
+            depth,
+            mut ancestors,
+        } = self.pending.pop_front()?;
+        let mut listing = list_directory(&path, &self.options);
+        let deeper = self.options.max_depth.is_none_or(|max| depth + 1 < max);
+        if let (Ok(paths), true) = (&mut listing, deeper) {
+            let mut children = paths.subdirectories();
+            // Only followed links can lead back up
+            if self.options.follow_symlinks {
+                ancestors.extend(fs::canonicalize(&path));
+                children.retain(|dir| {
+                    let looped = fs::canonicalize(dir).is_ok_and(|real| ancestors.contains(&real));
+                    if looped {
+                        paths.mark_loop(dir);
+                    }
+                    !looped
+                });
+            }
+            let children = children.into_iter().map(|dir| Pending {
+                path: dir,
+                depth: depth + 1,
+                ancestors: ancestors.clone(),
+            });
+            match self.options.traversal {
+                // Children go before the remaining siblings, keeping their order
+                Traversal::Dfs => {
+                    for child in children.rev() {
+                        self.pending.push_front(child);
+                    }
+                }
+                Traversal::Bfs => self.pending.extend(children),
+            }
+        }
+        Some(Visit {
+            path,
+            depth,
+            listing,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::path::layout::Scratch;
+
+    fn headers(root: &std::path::Path, traversal: Traversal) -> Vec<String> {
+        let options = Options {
+            traversal,
+            ..Default::default()
+        };
+        headers_with(root, &options)
+    }
+
+    fn headers_with(root: &std::path::Path, options: &Options) -> Vec<String> {
+        Walk::new(root, options)
+            .map(|visit| {
+                let relative = visit.path.strip_prefix(root).unwrap();
+                format!("{}:{}", visit.depth, relative.display())
+            })
+            .collect()
+    }
+
+    const LAYOUT: &[&str] = &["x/x1/", "y/y1/", ".hidden/", "file"];
+
+    #[test]
+    fn depth_first_should_descend_before_siblings() {
+        let root = Scratch::new("lsr_walk_dfs", LAYOUT);
+
+        let headers = headers(&root, Traversal::Dfs);
+
+        assert_eq!(headers, vec!["0:", "1:x", "2:x/x1", "1:y", "2:y/y1"]);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn followed_symlink_back_to_an_ancestor_should_not_loop() {
+        let root = Scratch::new("lsr_walk_loop", LAYOUT);
+        std::os::unix::fs::symlink("..", root.join("x").join("back")).unwrap();
+        std::os::unix::fs::symlink("y", root.join("to_y")).unwrap();
+        let options = Options {
+            long: true,
+            follow_symlinks: true,
+            ..Default::default()
+        };
+
+        let visits: Vec<_> = Walk::new(&*root, &options).collect();
+
+        let headers: Vec<_> = visits
+            .iter()
+            .map(|visit| visit.path.strip_prefix(&root).unwrap().to_path_buf())
+            .collect();
+        // y is reached through to_y first, but isn't an ancestor, so it is
+        // still listed on its own
+        assert_eq!(
+            headers,
+            ["", "to_y", "to_y/y1", "x", "x/x1", "y", "y/y1"].map(PathBuf::from)
+        );
+        let x = visits[3].listing.as_ref().unwrap().render();
+        assert!(x.contains(" -> .. (loop)"), "{x}");
+    }
+
+    #[test]
+    fn breadth_first_should_go_level_by_level() {
+        let root = Scratch::new("lsr_walk_bfs", LAYOUT);
+
+        let headers = headers(&root, Traversal::Bfs);
+
+        assert_eq!(headers, vec!["0:", "1:x", "1:y", "2:x/x1", "2:y/y1"]);
+    }
+
+    #[test]
+    fn max_depth_should_stop_descending() {
+        let root = Scratch::new("lsr_walk_max_depth", LAYOUT);
+        let depth = |max_depth| Options {
+            max_depth: Some(max_depth),
+            ..Default::default()
+        };
+
+        let one = headers_with(&root, &depth(1));
+        let two = headers_with(&root, &depth(2));
+
+        assert_eq!(one, vec!["0:"]);
+        assert_eq!(two, vec!["0:", "1:x", "1:y"]);
+    }
+}
@@ -0,0 +1,911 @@
+use std::process::Command;
+
+/// The binary, with no `COLUMNS` inherited from the shell running the tests.
+fn lsr() -> Command {
+    let mut lsr = Command::new(env!("CARGO_BIN_EXE_lsr"));
+    lsr.env_remove("COLUMNS");
+    lsr
+}
+
+#[test]
+fn existing_directory_should_exit_0() {
+    let output = lsr().arg("src").output().unwrap();
+
+    assert_eq!(output.status.code(), Some(0));
+    assert!(output.stderr.is_empty());
+}
+
+#[test]
+fn missing_path_should_exit_2() {
+    let output = lsr().arg("does-not-exist").output().unwrap();
+
+    assert_eq!(output.status.code(), Some(2));
+    assert!(String::from_utf8_lossy(&output.stderr).contains("cannot access 'does-not-exist'"));
+    assert!(output.stdout.is_empty());
+}
+
+#[test]
+fn missing_path_should_still_list_the_others() {
+    let output = lsr().args(["does-not-exist", "src"]).output().unwrap();
+
+    assert_eq!(output.status.code(), Some(2));
+    assert!(String::from_utf8_lossy(&output.stdout).contains("main.rs"));
+}
+
+#[test]
+fn quiet_should_keep_the_exit_code() {
+    let output = lsr().args(["--quiet", "does-not-exist"]).output().unwrap();
+
+    assert_eq!(output.status.code(), Some(2));
+    assert!(output.stderr.is_empty());
+}
+
+#[test]
+fn file_argument_should_be_listed() {
+    let output = lsr().arg("Cargo.toml").output().unwrap();
+
+    assert_eq!(output.status.code(), Some(0));
+    assert_eq!(String::from_utf8_lossy(&output.stdout).trim(), "Cargo.toml");
+}
+
+#[test]
+fn no_expand_should_not_list_directory_contents() {
+    let output = lsr()
+        .args(["--no-expand", "src", "missing", "Cargo.toml"])
+        .output()
+        .unwrap();
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert_eq!(
+        stdout.lines().collect::<Vec<_>>(),
+        vec!["Cargo.toml", "src"]
+    );
+    assert!(String::from_utf8_lossy(&output.stderr).contains("cannot access 'missing'"));
+}
+
+fn assert_invalid(args: &[&str], value: &str, accepted: &str) {
+    let output = lsr().args(args).output().unwrap();
+    let stderr = String::from_utf8_lossy(&output.stderr);
+
+    assert_eq!(output.status.code(), Some(2), "{args:?}");
+    assert!(stderr.contains(value), "{stderr}");
+    assert!(stderr.contains(accepted), "{stderr}");
+    assert!(output.stdout.is_empty());
+}
+
+#[test]
+fn invalid_enum_values_should_exit_2_and_list_accepted_values() {
+    assert_invalid(
+        &["--sort=bogus"],
+        "'bogus'",
+        "name, size, time, length, version, random, none",
+    );
+    assert_invalid(
+        &["--color", "sometimes"],
+        "'sometimes'",
+        "auto, always, never",
+    );
+    assert_invalid(&["--time-style=iso"], "'iso'", "default, full-iso");
+    assert_invalid(&["--block-size=X"], "'X'", "K, M, G, KB, MB, GB");
+    assert_invalid(&["--type=f,x"], "'x'", "f, d, l, p, s, b, c");
+}
+
+#[test]
+fn unknown_flag_should_exit_2() {
+    let output = lsr().arg("--frobnicate").output().unwrap();
+
+    assert_eq!(output.status.code(), Some(2));
+    assert!(String::from_utf8_lossy(&output.stderr).contains("--frobnicate"));
+}
+
+#[test]
+fn help_should_exit_0() {
+    let output = lsr().arg("--help").output().unwrap();
+
+    assert_eq!(output.status.code(), Some(0));
+    assert!(!output.stdout.is_empty());
+}
+
+fn listed(dir: &str, args: &[&str]) -> Vec<String> {
+    let output = lsr().current_dir(dir).args(args).output().unwrap();
+    assert_eq!(output.status.code(), Some(0), "{args:?}");
+    String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .map(str::to_string)
+        .collect()
+}
+
+#[test]
+fn dot_and_dot_dot_should_list_the_resolved_directory() {
+    assert!(listed("src", &["."]).contains(&"main.rs".to_string()));
+    assert!(listed("src", &["./"]).contains(&"main.rs".to_string()));
+    assert!(listed("src", &[".."]).contains(&"Cargo.toml".to_string()));
+    assert!(listed("src/cmd", &["../.."]).contains(&"Cargo.toml".to_string()));
+}
+
+#[test]
+fn trailing_slash_should_list_the_directory_under_its_given_name() {
+    let lines = listed(".", &["src/cmd/", "src/./path"]);
+
+    assert_eq!(lines[0], "src/cmd/:");
+    assert!(lines.contains(&"cli.rs".to_string()));
+    assert!(lines.contains(&"src/./path:".to_string()));
+    assert!(lines.contains(&"paths.rs".to_string()));
+}
+
+#[cfg(unix)]
+#[test]
+fn symlink_to_directory_argument_should_be_followed_with_h() {
+    let dir = std::env::temp_dir().join("lsr_cli_dereference_args");
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(dir.join("target").join("nested")).unwrap();
+    std::fs::write(dir.join("target").join("inside"), "").unwrap();
+    std::os::unix::fs::symlink("target", dir.join("link")).unwrap();
+    std::os::unix::fs::symlink("target", dir.join("target").join("loop")).unwrap();
+    let run = |args: &[&str]| {
+        let output = lsr()
+            .current_dir(&dir)
+            .args(["--color=never"])
+            .args(args)
+            .output()
+            .unwrap();
+        String::from_utf8_lossy(&output.stdout).into_owned()
+    };
+
+    let short = run(&["link"]);
+    let long = run(&["-l", "link"]);
+    let followed = run(&["-l", "-H", "link"]);
+    let recursive = run(&["-R", "-H", "link"]);
+    std::fs::remove_dir_all(&dir).unwrap();
+
+    assert_eq!(short, "inside\nloop\nnested\n");
+    assert!(long.trim_end().ends_with("link -> target"), "{long}");
+    assert_eq!(followed.lines().count(), 3, "{followed}");
+    assert!(followed.contains("loop -> target"), "{followed}");
+    // Links found while walking stay links
+    assert_eq!(recursive, "link:\ninside\nloop\nnested\n\nlink/nested:\n");
+}
+
+/// A directory holding empty files with these names, so layout tests don't
+/// depend on what the source tree happens to contain.
+fn fixture(name: &str, files: &[&str]) -> std::path::PathBuf {
+    let dir = std::env::temp_dir().join(name);
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).unwrap();
+    for file in files {
+        std::fs::write(dir.join(file), "").unwrap();
+    }
+    dir
+}
+
+fn stdout(dir: &std::path::Path, args: &[&str]) -> String {
+    let output = lsr()
+        .arg("--color=never")
+        .args(args)
+        .arg(dir)
+        .output()
+        .unwrap();
+    String::from_utf8_lossy(&output.stdout).into_owned()
+}
+
+#[test]
+fn commas_should_fill_80_columns_without_a_terminal() {
+    let dir = fixture("lsr_cli_commas", &["cli.rs", "mod.rs", "status.rs"]);
+
+    let out = stdout(&dir, &["-m"]);
+    std::fs::remove_dir_all(&dir).unwrap();
+
+    assert_eq!(out, "cli.rs, mod.rs, status.rs\n");
+}
+
+#[test]
+fn columns_should_set_the_width_of_piped_output() {
+    let dir = fixture("lsr_cli_columns_env", &["cli.rs", "mod.rs", "status.rs"]);
+
+    let output = lsr()
+        .env("COLUMNS", "20")
+        .args(["--color=never", "-m"])
+        .arg(&dir)
+        .output()
+        .unwrap();
+    std::fs::remove_dir_all(&dir).unwrap();
+
+    // Captured by the test, so stdout is a pipe rather than a terminal
+    let out = String::from_utf8_lossy(&output.stdout);
+    assert_eq!(out, "cli.rs, mod.rs,\nstatus.rs\n");
+}
+
+#[test]
+fn output_width_should_replace_the_terminal_width() {
+    let dir = fixture("lsr_cli_output_width", &["cli.rs", "mod.rs", "status.rs"]);
+
+    let grid = stdout(&dir, &["--output-width=24"]);
+    let commas = stdout(&dir, &["-m", "--output-width=16"]);
+    std::fs::remove_dir_all(&dir).unwrap();
+
+    assert_eq!(grid, "cli.rs  status.rs\nmod.rs\n");
+    assert_eq!(commas, "cli.rs, mod.rs,\nstatus.rs\n");
+}
+
+#[test]
+fn unknown_column_should_exit_2() {
+    assert_invalid(
+        &["-l", "--columns=perms,inode,name"],
+        "'inode'",
+        "perms, links, owner, size, time, name, ext, frag, target",
+    );
+}
+
+#[test]
+fn errors_should_read_like_ls() {
+    let output = lsr().arg("does-not-exist").output().unwrap();
+
+    assert_eq!(
+        String::from_utf8_lossy(&output.stderr),
+        "lsr: cannot access 'does-not-exist': No such file or directory\n"
+    );
+}
+
+#[cfg(unix)]
+#[test]
+fn unreadable_directory_should_exit_2_and_list_the_others() {
+    use std::os::unix::fs::PermissionsExt;
+
+    // Root reads everything, so there is nothing to deny
+    // SAFETY: geteuid has no preconditions and can't fail.
+    if unsafe { libc::geteuid() } == 0 {
+        return;
+    }
+    let dir = fixture("lsr_cli_denied", &[]);
+    std::fs::set_permissions(&dir, std::fs::Permissions::from_mode(0o000)).unwrap();
+
+    let output = lsr().arg(&dir).arg("src").output().unwrap();
+    std::fs::set_permissions(&dir, std::fs::Permissions::from_mode(0o755)).unwrap();
+    std::fs::remove_dir_all(&dir).unwrap();
+
+    assert_eq!(output.status.code(), Some(2));
+    assert_eq!(
+        String::from_utf8_lossy(&output.stderr),
+        format!(
+            "lsr: cannot open directory '{}': Permission denied\n",
+            dir.display()
+        )
+    );
+    assert!(String::from_utf8_lossy(&output.stdout).contains("main.rs"));
+}
+
+#[test]
+fn human_readable_sizes_should_match_gnu() {
+    let dir = fixture("lsr_cli_human", &[]);
+    std::fs::write(dir.join("file"), vec![0; 1536]).unwrap();
+
+    let binary = stdout(&dir, &["-l", "-h", "--columns=size,name"]);
+    let si = stdout(&dir, &["-l", "--si", "--columns=size,name"]);
+    std::fs::remove_dir_all(&dir).unwrap();
+
+    assert_eq!(binary, "1.5K  file\n");
+    assert_eq!(si, "1.6k  file\n");
+}
+
+#[test]
+fn no_size_and_no_time_should_drop_their_columns() {
+    let dir = fixture("lsr_cli_no_columns", &["file"]);
+
+    let no_time = stdout(&dir, &["-l", "--columns=size,time,name", "--no-time"]);
+    let neither = stdout(
+        &dir,
+        &["-l", "--no-size", "--no-time", "--columns=size,time,name"],
+    );
+    std::fs::remove_dir_all(&dir).unwrap();
+
+    assert_eq!(no_time, "0B  file\n");
+    assert_eq!(neither, "file\n");
+}
+
+#[test]
+fn separator_with_no_align_should_give_splittable_lines() {
+    let dir = fixture("lsr_cli_separator", &["a", "longer"]);
+
+    let args = ["-l", "--columns=name,size", "--no-align", "--separator=\\t"];
+    let out = stdout(&dir, &args);
+    std::fs::remove_dir_all(&dir).unwrap();
+
+    assert_eq!(out, "a\t0B\nlonger\t0B\n");
+}
+
+#[test]
+fn indent_should_nest_each_level_of_a_recursive_listing() {
+    let dir = fixture("lsr_cli_indent", &["top"]);
+    std::fs::create_dir_all(dir.join("sub").join("deep")).unwrap();
+    std::fs::write(dir.join("sub").join("inner"), "").unwrap();
+
+    let out = stdout(&dir, &["-R", "--indent=2"]);
+    let shallow = stdout(&dir, &["-R", "--indent=2", "--max-depth=2"]);
+    std::fs::remove_dir_all(&dir).unwrap();
+
+    let root = dir.display();
+    assert_eq!(
+        out,
+        format!(
+            "{root}:\n  sub\n  top\n  {root}/sub:\n    deep\n    inner\n    {root}/sub/deep:\n"
+        )
+    );
+    assert_eq!(
+        shallow,
+        format!("{root}:\n  sub\n  top\n  {root}/sub:\n    deep\n    inner\n")
+    );
+}
+
+#[test]
+fn ext_column_should_align_extensions_before_the_name() {
+    let dir = fixture(
+        "lsr_cli_ext",
+        &["main.rs", "Cargo.toml", "LICENSE", ".bashrc"],
+    );
+    std::fs::create_dir(dir.join("src.d")).unwrap();
+
+    let out = stdout(&dir, &["-l", "-a", "--columns=name", "--ext-column"]);
+    std::fs::remove_dir_all(&dir).unwrap();
+
+    assert_eq!(
+        out,
+        "     .bashrc\ntoml Cargo.toml\n     LICENSE\nrs   main.rs\n     src.d\n"
+    );
+}
+
+#[test]
+fn quoted_glob_should_be_expanded_by_lsr() {
+    let dir = fixture("lsr_cli_glob", &["a.toml", "b.toml", "c.rs"]);
+    let pattern = format!("{}/*.toml", dir.display());
+    let nothing = format!("{}/*.md", dir.display());
+
+    let output = lsr().arg(&pattern).arg(&nothing).output().unwrap();
+    std::fs::remove_dir_all(&dir).unwrap();
+
+    assert_eq!(output.status.code(), Some(2));
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout),
+        format!("{0}/a.toml\n{0}/b.toml\n", dir.display())
+    );
+    assert_eq!(
+        String::from_utf8_lossy(&output.stderr),
+        format!("lsr: no matches for '{nothing}'\n")
+    );
+}
+
+#[test]
+fn scripting_format_should_print_tab_separated_fields() {
+    let dir = fixture("lsr_cli_scripting", &["empty"]);
+    std::fs::write(dir.join("data.bin"), vec![0; 2048]).unwrap();
+
+    let output = lsr()
+        .args(["--format=scripting", "--utc", "--color=always"])
+        .arg(&dir)
+        .output()
+        .unwrap();
+    std::fs::remove_dir_all(&dir).unwrap();
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let lines: Vec<Vec<&str>> = stdout
+        .lines()
+        .map(|line| line.split('\t').collect())
+        .collect();
+    assert_eq!(lines.len(), 2, "{stdout}");
+    for (fields, (bytes, name)) in lines.iter().zip([("2048", "data.bin"), ("0", "empty")]) {
+        assert_eq!(fields.len(), 5, "{stdout}");
+        assert!(fields[0].starts_with("-r"), "{stdout}");
+        assert_eq!(fields[1], bytes);
+        assert!(
+            fields[2].ends_with("+00:00") && fields[2].contains('T'),
+            "{stdout}"
+        );
+        assert_eq!(fields[3], name);
+        assert_eq!(fields[4], "-");
+    }
+}
+
+#[cfg(unix)]
+#[test]
+fn scripting_format_should_keep_names_and_targets_apart() {
+    let dir = fixture("lsr_cli_scripting_links", &[]);
+    std::fs::create_dir(dir.join("sub")).unwrap();
+    std::os::unix::fs::symlink("missing", dir.join("dangling")).unwrap();
+
+    let out = stdout(&dir, &["--format=scripting"]);
+    std::fs::remove_dir_all(&dir).unwrap();
+
+    let lines: Vec<Vec<&str>> = out.lines().map(|line| line.split('\t').collect()).collect();
+    assert_eq!(lines[0][3..], ["dangling", "missing"], "{out}");
+    assert_eq!(lines[1][3..], ["sub", "-"], "{out}");
+    assert!(lines[1][1].parse::<u64>().is_ok(), "{out}");
+}
+
+#[test]
+fn unknown_format_should_exit_2() {
+    assert_invalid(&["--format=fancy"], "'fancy'", "compact, long, scripting");
+}
+
+#[cfg(unix)]
+#[test]
+fn absolute_and_canonical_should_differ_on_symlinks() {
+    let dir = fixture("lsr_cli_absolute", &["file"]);
+    std::fs::create_dir(dir.join("real")).unwrap();
+    std::fs::write(dir.join("real").join("inner"), "").unwrap();
+    std::os::unix::fs::symlink("real", dir.join("link")).unwrap();
+    let real = std::fs::canonicalize(&dir).unwrap();
+
+    let absolute = stdout(&dir.join("link"), &["--absolute", "-1"]);
+    let canonical = stdout(&dir.join("link"), &["--canonical", "--zero"]);
+    std::fs::remove_dir_all(&dir).unwrap();
+
+    assert_eq!(absolute, format!("{}/link/inner\n", dir.display()));
+    assert_eq!(canonical, format!("{}/real/inner\0", real.display()));
+}
+
+#[test]
+fn relative_to_should_shorten_paths_inside_the_base() {
+    let dir = fixture("lsr_cli_relative_to", &[]);
+    std::fs::create_dir_all(dir.join("project").join("src")).unwrap();
+    std::fs::write(dir.join("project").join("src").join("main.rs"), "").unwrap();
+    std::fs::write(dir.join("outside"), "").unwrap();
+    let base = format!("--relative-to={}", dir.join("project").display());
+
+    let inside = stdout(&dir.join("project").join("src"), &["-1", &base]);
+    let outside = stdout(&dir, &["-1", &base]);
+    std::fs::remove_dir_all(&dir).unwrap();
+
+    assert_eq!(inside, "src/main.rs\n");
+    // The base itself is "." and anything outside it stays absolute
+    assert_eq!(outside, format!("{}\n.\n", dir.join("outside").display()));
+}
+
+#[test]
+fn relative_to_should_reject_a_missing_base() {
+    assert_invalid(
+        &["--relative-to=lsr_cli_no_such_base"],
+        "invalid value 'lsr_cli_no_such_base'",
+        "for '--relative-to <DIR>'",
+    );
+    assert_invalid(
+        &["--relative-to=Cargo.toml"],
+        "'Cargo.toml'",
+        "not a directory",
+    );
+}
+
+#[test]
+fn summarize_should_print_one_line_per_argument() {
+    let dir = fixture("lsr_cli_summarize", &[]);
+    std::fs::create_dir(dir.join("sub")).unwrap();
+    std::fs::write(dir.join("sub").join("data"), vec![0; 3000]).unwrap();
+    std::fs::write(dir.join("top"), vec![0; 100]).unwrap();
+
+    let output = lsr()
+        .args(["--summarize", "-h"])
+        .arg(&dir)
+        .arg(dir.join("top"))
+        .arg(dir.join("missing"))
+        .output()
+        .unwrap();
+    std::fs::remove_dir_all(&dir).unwrap();
+
+    assert_eq!(output.status.code(), Some(2));
+    // 3100 bytes rounded up; top was already counted as part of the directory
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout),
+        format!("3.1K\t{0}\n0\t{0}/top\n", dir.display())
+    );
+}
+
+#[cfg(unix)]
+#[test]
+fn size_blocks_should_lead_each_line_and_head_with_a_total() {
+    let dir = fixture("lsr_cli_size_blocks", &["empty"]);
+    std::fs::write(dir.join("data"), vec![1; 5000]).unwrap();
+
+    let output = lsr().arg("-s").arg(&dir).output().unwrap();
+    std::fs::remove_dir_all(&dir).unwrap();
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let lines: Vec<_> = stdout.lines().collect();
+    let blocks = |line: &str| -> u64 { line.split_whitespace().next().unwrap().parse().unwrap() };
+    assert_eq!(lines.len(), 3);
+    assert!(lines[1].ends_with(" data"));
+    assert!(lines[2].ends_with(" empty"));
+    // 5000 bytes take at least five 1K blocks wherever they are stored
+    assert!(blocks(lines[1]) >= 5);
+    assert_eq!(blocks(lines[2]), 0);
+    assert_eq!(lines[0], format!("total {}", blocks(lines[1])));
+}
+
+#[cfg(unix)]
+#[test]
+fn blocks_total_should_head_the_long_format_like_ls() {
+    let dir = fixture("lsr_cli_blocks_total", &["empty"]);
+    std::fs::write(dir.join("data"), vec![1; 5000]).unwrap();
+    std::fs::write(dir.join(".hidden"), vec![1; 5000]).unwrap();
+
+    let shown = stdout(&dir, &["-l", "--blocks-total", "--columns=name"]);
+    let all = stdout(&dir, &["-l", "-a", "--blocks-total", "--columns=name"]);
+    let blocks = stdout(&dir, &["-s", "-a"]);
+    std::fs::remove_dir_all(&dir).unwrap();
+
+    let lines: Vec<_> = shown.lines().collect();
+    assert_eq!(lines[1..], ["data", "empty"]);
+    let total = |line: &str| -> u64 { line.strip_prefix("total ").unwrap().parse().unwrap() };
+    // 5000 bytes take at least five 1K blocks wherever they are stored
+    assert!(total(lines[0]) >= 5);
+    // Hidden files count once they are listed, as -s would count them
+    assert!(total(all.lines().next().unwrap()) > total(lines[0]));
+    assert_eq!(all.lines().next(), blocks.lines().next());
+}
+
+#[test]
+fn merge_should_pool_directories_into_one_sorted_listing() {
+    let dir = fixture("lsr_cli_merge", &[]);
+    for (sub, files) in [("one", ["same", "x"]), ("two", ["same", "a"])] {
+        std::fs::create_dir(dir.join(sub)).unwrap();
+        for file in files {
+            std::fs::write(dir.join(sub).join(file), "").unwrap();
+        }
+    }
+
+    let output = lsr()
+        .current_dir(&dir)
+        .args(["--color=never", "--merge", "one", "two"])
+        .output()
+        .unwrap();
+    std::fs::remove_dir_all(&dir).unwrap();
+
+    // Sorted by name, with a tie keeping the order of the arguments
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout),
+        "two/a\none/same\ntwo/same\none/x\n"
+    );
+}
+
+#[test]
+fn dedup_should_split_copies_from_originals() {
+    let dir = fixture("lsr_cli_dedup", &[]);
+    for (name, content) in [("a", "one"), ("b", "two"), ("c", "one"), ("d", "one")] {
+        std::fs::write(dir.join(name), content).unwrap();
+    }
+
+    let kept = stdout(&dir, &["--dedup"]);
+    let copies = stdout(&dir, &["--show-dups-only"]);
+    let reversed = stdout(&dir, &["--dedup", "--reverse"]);
+    std::fs::remove_dir_all(&dir).unwrap();
+
+    assert_eq!(kept, "a\nb\n");
+    assert_eq!(copies, "c\nd\n");
+    // The first one listed is the one kept
+    assert_eq!(reversed, "d\nb\n");
+}
+
+#[test]
+fn name_width_should_pad_and_cut_names() {
+    let dir = fixture("lsr_cli_name_width", &["a", "a_rather_long_name"]);
+
+    let out = stdout(
+        &dir,
+        &["-l", "--no-size", "--columns=name,perms", "--name-width=8"],
+    );
+    std::fs::remove_dir_all(&dir).unwrap();
+
+    let names: Vec<_> = out
+        .lines()
+        .map(|line| &line[..line.find('-').unwrap()])
+        .collect();
+    assert_eq!(names, ["a        ", "a_rathe… "]);
+}
+
+#[test]
+fn collapse_home_should_shorten_full_paths() {
+    let dir = fixture("lsr_cli_collapse_home", &["file"]);
+
+    let listing = |collapse: bool| {
+        let mut command = lsr();
+        command
+            .env("HOME", &dir)
+            .args(["--color=never", "--absolute"]);
+        if collapse {
+            command.arg("--collapse-home");
+        }
+        let output = command.arg(&dir).output().unwrap();
+        String::from_utf8_lossy(&output.stdout).into_owned()
+    };
+    let (full, collapsed) = (listing(false), listing(true));
+    std::fs::remove_dir_all(&dir).unwrap();
+
+    assert_eq!(full, format!("{}/file\n", dir.display()));
+    assert_eq!(collapsed, "~/file\n");
+}
+
+#[test]
+fn entries_per_line_should_fix_the_grid_columns() {
+    let dir = fixture("lsr_cli_entries_per_line", &["a", "b", "c", "d"]);
+
+    // Not a terminal, which would otherwise mean one name per line
+    let two = stdout(&dir, &["--entries-per-line=2"]);
+    let narrow = stdout(&dir, &["--entries-per-line=4", "--output-width=3"]);
+    std::fs::remove_dir_all(&dir).unwrap();
+
+    assert_eq!(two, "a  c\nb  d\n");
+    assert_eq!(narrow, "a  b  c  d\n");
+}
+
+#[test]
+fn numeric_sort_should_order_numbers_by_value() {
+    let dir = fixture("lsr_cli_numeric_sort", &["x10", "x9", "x01", "x1", "x100"]);
+
+    let plain = stdout(&dir, &[]);
+    let numeric = stdout(&dir, &["-v"]);
+    let version = stdout(&dir, &["--sort=version"]);
+    std::fs::remove_dir_all(&dir).unwrap();
+
+    assert_eq!(plain, "x01\nx1\nx10\nx100\nx9\n");
+    assert_eq!(numeric, "x01\nx1\nx9\nx10\nx100\n");
+    assert_eq!(version, numeric);
+}
+
+#[test]
+fn random_sort_should_repeat_with_the_same_seed() {
+    let names: Vec<String> = (0..20).map(|i| format!("file{i:02}")).collect();
+    let names: Vec<&str> = names.iter().map(String::as_str).collect();
+    let dir = fixture("lsr_cli_random", &names);
+
+    let first = stdout(&dir, &["-1", "--sort=random", "--seed=7"]);
+    let again = stdout(&dir, &["-1", "--sort=random", "--seed=7"]);
+    let other = stdout(&dir, &["-1", "--sort=random", "--seed=8"]);
+    std::fs::remove_dir_all(&dir).unwrap();
+
+    assert_eq!(first, again);
+    assert_ne!(first, other);
+    let mut sorted: Vec<_> = first.lines().collect();
+    assert_ne!(sorted, names);
+    sorted.sort();
+    assert_eq!(sorted, names);
+}
+
+#[test]
+fn explain_should_describe_the_options_on_stderr() {
+    let plain = lsr().arg("src").output().unwrap();
+    let output = lsr()
+        .args([
+            "--explain",
+            "-la",
+            "-h",
+            "--sort=size",
+            "-r",
+            "--ignore=*.o",
+        ])
+        .arg("src")
+        .output()
+        .unwrap();
+
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    let lines: Vec<_> = stderr.lines().collect();
+    assert!(lines[0].starts_with("layout: long, columns perms,links,owner,size,time,name"));
+    assert!(lines.contains(&"sort: size, reversed"));
+    assert!(lines.contains(&"hidden files: shown"));
+    assert!(lines.contains(&"ignore: *.o"));
+    assert!(lines.contains(&"sizes: human-readable, powers of 1024, apparent"));
+    assert!(lines.contains(&"output: text"));
+    assert_eq!(output.status.code(), Some(0));
+    assert!(!output.stdout.is_empty());
+    assert!(plain.stderr.is_empty());
+}
+
+#[cfg(unix)]
+#[test]
+fn symlinked_directory_argument_should_list_its_target() {
+    let dir = fixture("lsr_cli_symlink_argument", &[]);
+    std::fs::create_dir_all(dir.join("real").join("inner")).unwrap();
+    std::fs::write(dir.join("real").join("inner").join("deep"), "").unwrap();
+    std::os::unix::fs::symlink("real", dir.join("link")).unwrap();
+    std::fs::create_dir(dir.join("other")).unwrap();
+    std::fs::write(dir.join("other").join("far"), "").unwrap();
+    std::os::unix::fs::symlink("../other", dir.join("real").join("inner_link")).unwrap();
+    let link = dir.join("link");
+
+    let contents = stdout(&link, &[]);
+    let long = stdout(&link, &["-l", "--columns=perms,name"]);
+    let recursive = stdout(&link, &["-R"]);
+    let followed = stdout(&link, &["-R", "-L"]);
+    std::fs::remove_dir_all(&dir).unwrap();
+
+    // Named on its own, the link leads to what it points to, but the long
+    // format shows the link itself like ls -l does
+    assert_eq!(contents, "inner\ninner_link\n");
+    assert_eq!(long, format!("lrwxrwxrwx {} -> real\n", link.display()));
+    // Links inside the listing are only entered with -L
+    let name = link.display();
+    assert_eq!(
+        recursive,
+        format!("{name}:\ninner\ninner_link\n\n{name}/inner:\ndeep\n")
+    );
+    assert_eq!(
+        followed,
+        format!("{name}:\ninner\ninner_link\n\n{name}/inner:\ndeep\n\n{name}/inner_link:\nfar\n")
+    );
+}
+
+#[test]
+fn shell_should_quote_every_name_on_one_line() {
+    let dir = fixture("lsr_cli_shell", &["plain", "a b", "it's"]);
+
+    let out = stdout(&dir, &["--shell"]);
+    let absolute = stdout(&dir, &["--shell", "--absolute"]);
+    std::fs::remove_dir_all(&dir).unwrap();
+
+    assert_eq!(out, "'a b' 'it'\\''s' 'plain'\n");
+    let quoted = format!("'{}/plain'", dir.display());
+    assert!(absolute.trim_end().ends_with(&quoted), "{absolute}");
+}
+
+#[cfg(unix)]
+#[test]
+fn type_should_keep_only_the_kinds_asked_for() {
+    let dir = fixture("lsr_cli_type", &["file"]);
+    std::fs::create_dir(dir.join("sub")).unwrap();
+    std::os::unix::fs::symlink("file", dir.join("link")).unwrap();
+    let _socket = std::os::unix::net::UnixListener::bind(dir.join("socket")).unwrap();
+
+    let sockets = stdout(&dir, &["--type=s", "-l", "--columns=perms,name"]);
+    let mixed = stdout(&dir, &["-1", "--type=d,l"]);
+    let files = stdout(&dir, &["-1", "--type", "f"]);
+    std::fs::remove_dir_all(&dir).unwrap();
+
+    assert!(sockets.starts_with('s'), "{sockets}");
+    assert!(sockets.trim_end().ends_with(" socket"), "{sockets}");
+    assert_eq!(sockets.lines().count(), 1);
+    assert_eq!(mixed, "link\nsub\n");
+    assert_eq!(files, "file\n");
+}
+
+#[test]
+fn count_only_should_print_how_many_entries_are_shown() {
+    let dir = fixture(
+        "lsr_cli_count_only",
+        &["a.rs", "b.rs", "notes.md", ".hidden"],
+    );
+    std::fs::create_dir(dir.join("sub")).unwrap();
+    let ignored = fixture("lsr_cli_count_only_empty", &[".only_hidden"]);
+
+    let shown = stdout(&dir, &["--count-only"]);
+    let all = stdout(&dir, &["--count-only", "-a"]);
+    let filtered = stdout(&dir, &["--count-only", "--ignore=*.rs", "--type=f"]);
+    let output = lsr().arg("--count-only").arg(&ignored).output().unwrap();
+    std::fs::remove_dir_all(&dir).unwrap();
+    std::fs::remove_dir_all(&ignored).unwrap();
+
+    assert_eq!(shown, "4\n");
+    assert_eq!(all, "5\n");
+    assert_eq!(filtered, "1\n");
+    // Nothing to show is still a successful count
+    assert_eq!(output.status.code(), Some(0));
+    assert_eq!(String::from_utf8_lossy(&output.stdout), "0\n");
+}
+
+#[test]
+fn bsd_color_variables_should_be_honored() {
+    let dir = fixture("lsr_cli_lscolors", &["file"]);
+    std::fs::create_dir(dir.join("sub")).unwrap();
+
+    let run = |vars: &[(&str, &str)]| {
+        let mut lsr = lsr();
+        // Whatever the tests themselves run with mustn't count
+        for var in ["CLICOLOR", "CLICOLOR_FORCE", "NO_COLOR", "LSCOLORS"] {
+            lsr.env_remove(var);
+        }
+        let output = lsr
+            .arg("-1")
+            .arg(&dir)
+            .envs(vars.iter().copied())
+            .output()
+            .unwrap();
+        String::from_utf8_lossy(&output.stdout).into_owned()
+    };
+    let plain = run(&[]);
+    let forced = run(&[("CLICOLOR_FORCE", "1")]);
+    let bsd = run(&[("CLICOLOR_FORCE", "1"), ("LSCOLORS", "Bx")]);
+    let invalid = run(&[("CLICOLOR_FORCE", "1"), ("LSCOLORS", "??")]);
+    std::fs::remove_dir_all(&dir).unwrap();
+
+    // Not a terminal, so only CLICOLOR_FORCE brings colors
+    assert_eq!(plain, "file\nsub\n");
+    assert!(forced.contains("\u{1b}[34msub"), "{forced:?}");
+    // Bold red directories, with files left to the theme
+    assert!(bsd.contains("\u{1b}[1;31msub"), "{bsd:?}");
+    assert_eq!(invalid, forced);
+}
+
+#[test]
+fn total_should_add_up_the_sizes_shown() {
+    let dir = fixture("lsr_cli_total", &[]);
+    std::fs::write(dir.join("a"), vec![0; 500]).unwrap();
+    std::fs::create_dir(dir.join("sub")).unwrap();
+    std::fs::write(dir.join("sub").join("f"), vec![0; 3000]).unwrap();
+
+    let plain = stdout(&dir, &["--total"]);
+    let measured = stdout(&dir, &["--total", "--dir-sizes"]);
+    std::fs::remove_dir_all(&dir).unwrap();
+
+    // Without --dir-sizes the directory shows no size, so it adds none
+    assert!(plain.ends_with("total size: 500B\n"), "{plain}");
+    assert!(measured.ends_with("total size: 3KB\n"), "{measured}");
+}
+
+#[test]
+fn child_count_should_follow_the_type_marker_uncut() {
+    let dir = fixture("lsr_cli_child_count", &["file"]);
+    std::fs::create_dir(dir.join("empty")).unwrap();
+
+    let marked = stdout(&dir, &["-F", "--child-count"]);
+    let capped = stdout(&dir, &["-F", "--child-count", "--max-name-bytes=4"]);
+    std::fs::remove_dir_all(&dir).unwrap();
+
+    assert_eq!(marked, "empty/ [0]\nfile\n");
+    assert_eq!(capped, "e…/ [0]\nfile\n");
+}
+
+#[test]
+fn empty_marker_should_follow_the_type_marker_uncut() {
+    let dir = fixture("lsr_cli_mark_empty", &["file"]);
+    std::fs::create_dir(dir.join("empty")).unwrap();
+
+    let marked = stdout(&dir, &["-F", "--mark-empty"]);
+    let capped = stdout(&dir, &["-F", "--mark-empty", "--max-name-bytes=4"]);
+    std::fs::remove_dir_all(&dir).unwrap();
+
+    assert_eq!(marked, "empty/ (empty)\nfile\n");
+    assert_eq!(capped, "e…/ (empty)\nfile\n");
+}
+
+#[test]
+fn full_paths_should_keep_child_counts() {
+    let dir = fixture("lsr_cli_full_path_counts", &[]);
+    std::fs::create_dir(dir.join("sub")).unwrap();
+    let other = fixture("lsr_cli_full_path_counts_other", &[]);
+
+    let absolute = stdout(&dir, &["--absolute", "--child-count"]);
+    let merged = lsr()
+        .args(["--color=never", "--merge", "--child-count"])
+        .arg(&dir)
+        .arg(&other)
+        .output()
+        .unwrap();
+    std::fs::remove_dir_all(&dir).unwrap();
+    std::fs::remove_dir_all(&other).unwrap();
+
+    let sub = dir.join("sub");
+    assert_eq!(absolute, format!("{} [0]\n", sub.display()));
+    let merged = String::from_utf8_lossy(&merged.stdout);
+    assert_eq!(merged, format!("{} [0]\n", sub.display()));
+}
+
+#[test]
+fn last_column_should_not_be_padded() {
+    let dir = fixture("lsr_cli_last_column", &["a", "long_name"]);
+
+    let size_last = stdout(&dir, &["-l", "--columns=name,size"]);
+    let empty_last = stdout(&dir, &["-l", "--columns=size,ext"]);
+    std::fs::remove_dir_all(&dir).unwrap();
+
+    assert_eq!(size_last, "a         0B\nlong_name 0B\n");
+    assert_eq!(empty_last, "0B\n0B\n");
+}
+
+#[test]
+fn trailing_spaces_should_stay_in_names() {
+    let dir = fixture("lsr_cli_trailing_space", &["trail", "trail "]);
+
+    let long = stdout(&dir, &["-l", "--columns=size,name"]);
+    let grid = stdout(&dir, &["--output-width=80"]);
+    let commas = stdout(&dir, &["-m"]);
+    std::fs::remove_dir_all(&dir).unwrap();
+
+    assert_eq!(long, "0B  trail\n0B  trail \n");
+    assert_eq!(grid, "trail  trail \n");
+    assert_eq!(commas, "trail, trail \n");
+}